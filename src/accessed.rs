@@ -0,0 +1,121 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use std::{fmt::Display, time::SystemTime};
+
+/// Where a project's last-accessed time came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AccessedSource {
+    #[default]
+    Atime,
+    Mtime,
+    Created,
+}
+
+impl Display for AccessedSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessedSource::Atime => write!(f, "atime"),
+            AccessedSource::Mtime => write!(f, "mtime"),
+            AccessedSource::Created => write!(f, "created date"),
+        }
+    }
+}
+
+fn start_of(date: NaiveDate) -> DateTime<Local> {
+    date.and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(Local::now)
+}
+
+/// Pick the first plausible time out of atime, mtime and the created date.
+/// Filesystems without atime report the epoch, and nothing can be accessed before it was created.
+pub fn resolve(
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    created: NaiveDate,
+) -> (DateTime<Local>, AccessedSource) {
+    let plausible = |time: Option<SystemTime>| {
+        time.map(DateTime::<Local>::from)
+            .filter(|time| time.date_naive() >= created)
+    };
+    if let Some(time) = plausible(accessed) {
+        return (time, AccessedSource::Atime);
+    }
+    if let Some(time) = plausible(modified) {
+        return (time, AccessedSource::Mtime);
+    }
+    (start_of(created), AccessedSource::Created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn noon(date: NaiveDate) -> SystemTime {
+        let time = date.and_hms_opt(12, 0, 0).unwrap();
+        time.and_local_timezone(Local).unwrap().into()
+    }
+
+    /// Resolve against a project created on 2024-03-10.
+    fn resolve_at(
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> (DateTime<Local>, AccessedSource) {
+        resolve(accessed, modified, date(2024, 3, 10))
+    }
+
+    #[test]
+    fn prefers_a_plausible_atime() {
+        let accessed = noon(date(2024, 5, 1));
+        let modified = noon(date(2024, 4, 1));
+        assert_eq!(
+            resolve_at(Some(accessed), Some(modified)),
+            (accessed.into(), AccessedSource::Atime)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_mtime_when_atime_is_missing_or_before_creation() {
+        let modified = noon(date(2024, 4, 1));
+        let expected = (modified.into(), AccessedSource::Mtime);
+        assert_eq!(resolve_at(None, Some(modified)), expected);
+        assert_eq!(
+            resolve_at(Some(SystemTime::UNIX_EPOCH), Some(modified)),
+            expected
+        );
+        assert_eq!(
+            resolve_at(Some(noon(date(2024, 3, 9))), Some(modified)),
+            expected
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_created_date_when_neither_is_plausible() {
+        let expected = (start_of(date(2024, 3, 10)), AccessedSource::Created);
+        assert_eq!(resolve_at(None, None), expected);
+        assert_eq!(
+            resolve_at(Some(SystemTime::UNIX_EPOCH), Some(SystemTime::UNIX_EPOCH)),
+            expected
+        );
+        assert_eq!(
+            resolve_at(Some(noon(date(2020, 1, 1))), Some(noon(date(2024, 3, 9)))),
+            expected
+        );
+        assert_eq!(expected.0.date_naive(), date(2024, 3, 10));
+    }
+
+    #[test]
+    fn the_creation_day_itself_is_plausible() {
+        let accessed = start_of(date(2024, 3, 10)) + Duration::from_secs(1);
+        let accessed = SystemTime::from(accessed);
+        assert_eq!(
+            resolve_at(Some(accessed), None),
+            (accessed.into(), AccessedSource::Atime)
+        );
+    }
+}
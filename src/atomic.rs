@@ -0,0 +1,154 @@
+use color_eyre::eyre::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::paths::Paths;
+
+/// Exclusive advisory lock serializing read-modify-write cycles across vpm processes.
+/// The lock is released when this is dropped.
+#[derive(Debug)]
+pub struct Lock {
+    _file: File,
+}
+
+/// Take vpm's lock, the one in its state directory.
+pub fn lock() -> Result<Lock> {
+    lock_at(&Paths::resolve()?.lock_file())
+}
+
+/// Take the lock on the file at `path`, creating it if needed.
+pub fn lock_at(path: &Path) -> Result<Lock> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    file.lock()?;
+    Ok(Lock { _file: file })
+}
+
+/// Replace `path` with `contents` so that readers only ever see the old or the new file.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = dir.join(format!(".{file_name}.{}.tmp", std::process::id()));
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    match written.and_then(|_| fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&temp);
+            Err(err)
+        }
+    }
+}
+
+/// Read a vpm-owned TOML file, falling back to the default if it is missing.
+/// A corrupt file is moved aside with a warning instead of failing the command.
+pub fn read_toml<T: DeserializeOwned + Default>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => return Err(err.into()),
+    };
+    match toml::from_str(&contents) {
+        Ok(value) => Ok(value),
+        Err(err) => {
+            let backup = corrupt_path(path);
+            eprintln!(
+                "Warning: ignoring corrupt {} ({}), moved it to {}",
+                path.display(),
+                err.message(),
+                backup.display()
+            );
+            fs::rename(path, backup)?;
+            Ok(T::default())
+        }
+    }
+}
+
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+/// Read, modify and write back a vpm-owned TOML file while holding the lock at `lock`.
+pub fn update<T: DeserializeOwned + Serialize + Default>(
+    lock: &Path,
+    path: impl AsRef<Path>,
+    modify: impl FnOnce(&mut T),
+) -> Result<()> {
+    let _lock = lock_at(lock)?;
+    let mut value = read_toml(&path)?;
+    modify(&mut value);
+    write(path, toml::to_string(&value)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{meta, test_util::TempDir};
+    use std::{
+        collections::BTreeSet,
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    const WRITERS: usize = 8;
+    const UPDATES: usize = 25;
+
+    #[test]
+    fn concurrent_meta_updates_are_never_lost_or_torn() {
+        let dir = TempDir::new();
+        let lock = dir.path().join("state/vpm.lock");
+        let project = dir.mkdir("p01-stress-2024-01-01");
+
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let reader = scope.spawn(|| {
+                let mut reads = 0;
+                while !done.load(Ordering::SeqCst) {
+                    meta::load(&project).expect("read a torn meta file");
+                    reads += 1;
+                }
+                reads
+            });
+            let writers = (0..WRITERS)
+                .map(|writer| {
+                    let (lock, project) = (&lock, &project);
+                    scope.spawn(move || {
+                        for update in 0..UPDATES {
+                            meta::update_at(lock, project, |meta| {
+                                meta.tags.push(format!("w{writer}-{update}"));
+                            })
+                            .unwrap();
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            done.store(true, Ordering::SeqCst);
+            assert!(reader.join().unwrap() > 0);
+        });
+
+        let tags = meta::load(&project).unwrap().unwrap().tags;
+        let unique = tags.iter().collect::<BTreeSet<_>>();
+        assert_eq!(tags.len(), WRITERS * UPDATES);
+        assert_eq!(unique.len(), WRITERS * UPDATES);
+    }
+}
@@ -0,0 +1,28 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::process::Command;
+
+/// The text on the system clipboard, via `pbpaste` on macOS and `xclip` elsewhere.
+pub fn read_from_clipboard() -> Result<String> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("pbpaste", &[])
+    } else {
+        ("xclip", &["-o", "-selection", "clipboard"])
+    };
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|err| anyhow!("Failed to run {program}: {err}"))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let text = String::from_utf8(output.stdout)
+        .map_err(|_| anyhow!("The clipboard does not contain text!"))?;
+    if text.trim().is_empty() {
+        return Err(anyhow!("The clipboard is empty!"));
+    }
+    Ok(text)
+}
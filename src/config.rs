@@ -0,0 +1,563 @@
+use clap::ValueEnum;
+use color_eyre::eyre::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atomic,
+    health::{self, HealthCheck},
+    kind::{self, KindRule},
+    paths::Paths,
+    sort::Sort,
+    when::Span,
+};
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_EDITOR: &str = "code";
+pub const DEFAULT_TUI_SORT: &str = "accessed";
+const DEFAULT_LIST_SORT: &str = "id";
+const DEFAULT_TRASH_RETENTION: &str = "30d";
+const DEFAULT_SCAN_MAX_SKIPPED: &str = "0.5";
+
+/// The contents of `config.toml`. Every key is optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_home: Option<String>,
+    #[serde(default)]
+    pub editor: EditorConfig,
+    #[serde(default)]
+    pub tui: TuiConfig,
+    #[serde(default)]
+    pub list: ListConfig,
+    #[serde(default)]
+    pub trash: TrashConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    /// Extra kind inference rules, tried before the built-in ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<KindRule>,
+    /// Extra health checks, run after the built-in ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub health: Vec<HealthCheck>,
+}
+
+/// The `[editor]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditorConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Command opening a project next to its notes, with `{path}` and `{notes}` placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_command: Option<String>,
+}
+
+/// The `[tui]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TuiConfig {
+    /// How the TUI orders projects, `accessed` (most recent first) by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_sort: Option<String>,
+}
+
+/// The `[list]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListConfig {
+    /// Comma-separated columns `vpm list` sorts by without `--sort`, `id` by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_sort: Option<String>,
+}
+
+/// The `[trash]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrashConfig {
+    /// How long trashed projects are kept, e.g. `30d`, before vpm suggests emptying the trash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<String>,
+    /// Delete trashed projects past the retention instead of only suggesting it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_empty: Option<bool>,
+}
+
+/// The `[display]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// Set to `false` to never print colours. Colours are used on terminals otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<bool>,
+    /// Comma-separated display flags that are always on, e.g. `id,date` for `vpm -i -d`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<String>,
+}
+
+/// The `[scan]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanConfig {
+    /// Fraction of the folders in the status folders that may have an unexpected name before
+    /// vpm warns about it, `0.5` by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_skipped: Option<String>,
+}
+
+/// The `[git]` table of `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitConfig {
+    /// File copied to `.gitignore` by `vpm git-init` without `--gitignore`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitignore: Option<String>,
+}
+
+impl ConfigFile {
+    fn field(&mut self, key: &str) -> Result<&mut Option<String>> {
+        match key {
+            "project_home" => Ok(&mut self.project_home),
+            "editor.command" => Ok(&mut self.editor.command),
+            "editor.split_command" => Ok(&mut self.editor.split_command),
+            "tui.default_sort" => Ok(&mut self.tui.default_sort),
+            "list.default_sort" => Ok(&mut self.list.default_sort),
+            "display.flags" => Ok(&mut self.display.flags),
+            "trash.retention" => Ok(&mut self.trash.retention),
+            "scan.max_skipped" => Ok(&mut self.scan.max_skipped),
+            "git.gitignore" => Ok(&mut self.git.gitignore),
+            _ => Err(unknown_key(key)),
+        }
+    }
+}
+
+fn unknown_key(key: &str) -> color_eyre::eyre::Error {
+    anyhow!(
+        "Unknown config key `{key}`, expected one of: {}",
+        KEYS.join(", ")
+    )
+}
+
+/// Where a resolved setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Flag(&'static str),
+    File(PathBuf),
+    Env(&'static str),
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Flag(flag) => write!(f, "from {flag}"),
+            Source::File(path) => write!(f, "from {}", path.display()),
+            Source::Env(var) => write!(f, "from ${var}"),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Setting {
+    pub value: Option<String>,
+    pub source: Source,
+}
+
+/// The configuration resolved from environment variables, the config file and defaults.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub path: PathBuf,
+    pub project_home: Setting,
+    pub editor: Setting,
+    pub split_command: Setting,
+    pub tui_sort: Setting,
+    pub list_sort: Setting,
+    pub display_flags: Setting,
+    pub trash_retention: Setting,
+    pub trash_auto_empty: Setting,
+    pub color: Setting,
+    pub scan_max_skipped: Setting,
+    pub gitignore: Setting,
+    pub kind_rules: Vec<KindRule>,
+    pub health_checks: Vec<HealthCheck>,
+}
+
+pub const KEYS: &[&str] = &[
+    "project_home",
+    "editor.command",
+    "editor.split_command",
+    "tui.default_sort",
+    "list.default_sort",
+    "display.flags",
+    "trash.retention",
+    "trash.auto_empty",
+    "display.color",
+    "scan.max_skipped",
+    "git.gitignore",
+];
+
+pub fn load_file(path: &Path) -> Result<ConfigFile> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(toml::from_str(&contents)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(ConfigFile::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn resolve(path: &Path, var: Option<&'static str>, file_value: Option<String>) -> Setting {
+    match var.map(|var| (var, env::var(var))) {
+        Some((var, Ok(value))) => Setting {
+            value: Some(value),
+            source: Source::Env(var),
+        },
+        _ => match file_value {
+            Some(value) => Setting {
+                value: Some(value),
+                source: Source::File(path.to_path_buf()),
+            },
+            None => Setting {
+                value: None,
+                source: Source::Default,
+            },
+        },
+    }
+}
+
+impl Config {
+    pub fn load(paths: &Paths) -> Result<Self> {
+        let path = paths.config_file();
+        let file = load_file(&path)?;
+        let project_home = resolve(&path, Some("PROJECT_HOME"), file.project_home);
+        let mut editor = resolve(&path, Some("VPM_EDITOR"), file.editor.command);
+        for var in ["VISUAL", "EDITOR"] {
+            if editor.value.is_none() {
+                editor = resolve(&path, Some(var), None);
+            }
+        }
+        editor
+            .value
+            .get_or_insert_with(|| DEFAULT_EDITOR.to_string());
+        let split_command = resolve(&path, None, file.editor.split_command);
+        let mut tui_sort = resolve(&path, None, file.tui.default_sort);
+        tui_sort
+            .value
+            .get_or_insert_with(|| DEFAULT_TUI_SORT.to_string());
+        let mut list_sort = resolve(&path, None, file.list.default_sort);
+        list_sort
+            .value
+            .get_or_insert_with(|| DEFAULT_LIST_SORT.to_string());
+        let display_flags = resolve(&path, None, file.display.flags);
+        let mut trash_retention = resolve(&path, None, file.trash.retention);
+        trash_retention
+            .value
+            .get_or_insert_with(|| DEFAULT_TRASH_RETENTION.to_string());
+        let mut trash_auto_empty = resolve(
+            &path,
+            None,
+            file.trash
+                .auto_empty
+                .map(|auto_empty| auto_empty.to_string()),
+        );
+        trash_auto_empty
+            .value
+            .get_or_insert_with(|| false.to_string());
+        let mut color = resolve(
+            &path,
+            None,
+            file.display.color.map(|color| color.to_string()),
+        );
+        color.value.get_or_insert_with(|| true.to_string());
+        let mut scan_max_skipped = resolve(&path, None, file.scan.max_skipped);
+        scan_max_skipped
+            .value
+            .get_or_insert_with(|| DEFAULT_SCAN_MAX_SKIPPED.to_string());
+        let gitignore = resolve(&path, None, file.git.gitignore);
+        let mut kind_rules = file.kinds;
+        kind_rules.extend(kind::default_rules());
+        let mut health_checks = health::default_checks();
+        health_checks.extend(file.health);
+        Ok(Self {
+            path,
+            project_home,
+            editor,
+            split_command,
+            tui_sort,
+            list_sort,
+            display_flags,
+            trash_retention,
+            trash_auto_empty,
+            color,
+            scan_max_skipped,
+            gitignore,
+            kind_rules,
+            health_checks,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Result<&Setting> {
+        match key {
+            "project_home" => Ok(&self.project_home),
+            "editor.command" => Ok(&self.editor),
+            "editor.split_command" => Ok(&self.split_command),
+            "tui.default_sort" => Ok(&self.tui_sort),
+            "list.default_sort" => Ok(&self.list_sort),
+            "display.flags" => Ok(&self.display_flags),
+            "trash.retention" => Ok(&self.trash_retention),
+            "trash.auto_empty" => Ok(&self.trash_auto_empty),
+            "display.color" => Ok(&self.color),
+            "scan.max_skipped" => Ok(&self.scan_max_skipped),
+            "git.gitignore" => Ok(&self.gitignore),
+            _ => Err(unknown_key(key)),
+        }
+    }
+
+    pub fn editor(&self) -> &str {
+        self.editor.value.as_deref().unwrap_or(DEFAULT_EDITOR)
+    }
+
+    /// How the TUI orders projects when it starts.
+    pub fn tui_sort(&self) -> Result<Sort> {
+        parse_sort(self.tui_sort.value.as_deref().unwrap_or(DEFAULT_TUI_SORT))
+    }
+
+    /// How `vpm list` orders projects without `--sort`.
+    pub fn list_sort(&self) -> Result<Vec<Sort>> {
+        parse_sorts(self.list_sort.value.as_deref().unwrap_or(DEFAULT_LIST_SORT))
+    }
+
+    /// The display flags that are always on, checked against `DISPLAY_FLAGS`.
+    pub fn display_flags(&self) -> Result<Vec<&str>> {
+        parse_flags(self.display_flags.value.as_deref().unwrap_or_default())
+    }
+
+    /// How long trashed projects are kept before vpm suggests or does emptying the trash.
+    pub fn trash_retention(&self) -> Result<Span> {
+        parse_retention(
+            self.trash_retention
+                .value
+                .as_deref()
+                .unwrap_or(DEFAULT_TRASH_RETENTION),
+        )
+    }
+
+    pub fn trash_auto_empty(&self) -> Result<bool> {
+        parse_bool(
+            "trash.auto_empty",
+            self.trash_auto_empty.value.as_deref().unwrap_or("false"),
+        )
+    }
+
+    /// Fraction of unparsable project folders above which vpm warns about the naming scheme.
+    pub fn scan_max_skipped(&self) -> Result<f64> {
+        parse_fraction(
+            "scan.max_skipped",
+            self.scan_max_skipped
+                .value
+                .as_deref()
+                .unwrap_or(DEFAULT_SCAN_MAX_SKIPPED),
+        )
+    }
+
+    /// Whether colours may be used at all, see `display::should_use_color`.
+    pub fn color(&self) -> Result<bool> {
+        parse_bool(
+            "display.color",
+            self.color.value.as_deref().unwrap_or("true"),
+        )
+    }
+
+    /// The command opening a project and its notes side by side, with placeholders filled in.
+    pub fn split_command(&self, path: &str, notes: &str) -> Vec<String> {
+        let template = match &self.split_command.value {
+            Some(template) => template.clone(),
+            None => format!("{} {{path}} {{notes}}", self.editor()),
+        };
+        template
+            .split_whitespace()
+            .map(|word| word.replace("{path}", path).replace("{notes}", notes))
+            .collect()
+    }
+
+    /// Render the resolved configuration as TOML, annotating each key with its source.
+    pub fn show(&self) -> Result<String> {
+        let mut out = String::new();
+        for key in KEYS {
+            let setting = self.get(key)?;
+            out.push_str(&format!("# {}\n", setting.source));
+            match &setting.value {
+                Some(value) => {
+                    out.push_str(&format!("{key} = {}\n", toml::Value::from(value.as_str())))
+                }
+                None => out.push_str(&format!("# {key} is not set\n")),
+            }
+        }
+        Ok(out)
+    }
+}
+
+pub fn parse_sort(value: &str) -> Result<Sort> {
+    Sort::from_str(value, true).map_err(|_| {
+        let names = Sort::value_variants()
+            .iter()
+            .filter_map(|sort| sort.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect::<Vec<_>>();
+        anyhow!(
+            "Invalid tui.default_sort `{value}`, expected one of: {}",
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_sorts(value: &str) -> Result<Vec<Sort>> {
+    value
+        .split(',')
+        .map(|sort| {
+            parse_sort(sort.trim()).map_err(|_| anyhow!("Invalid list.default_sort `{value}`"))
+        })
+        .collect()
+}
+
+/// Display flags `display.flags` can turn on, named like their long options.
+pub const DISPLAY_FLAGS: &[&str] = &[
+    "id",
+    "path",
+    "date",
+    "full-name",
+    "no-name",
+    "accessed",
+    "status",
+    "show-kind",
+    "description",
+];
+
+fn parse_flags(value: &str) -> Result<Vec<&str>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|flag| !flag.is_empty())
+        .map(|flag| match DISPLAY_FLAGS.contains(&flag) {
+            true => Ok(flag),
+            false => Err(anyhow!(
+                "Invalid display.flags `{flag}`, expected some of: {}",
+                DISPLAY_FLAGS.join(", ")
+            )),
+        })
+        .collect()
+}
+
+fn parse_retention(value: &str) -> Result<Span> {
+    value
+        .parse()
+        .map_err(|err| anyhow!("Invalid trash.retention `{value}`: {err}"))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid {key} `{value}`, expected `true` or `false`"))
+}
+
+fn parse_fraction(key: &str, value: &str) -> Result<f64> {
+    value
+        .parse()
+        .ok()
+        .filter(|fraction| (0.0..=1.0).contains(fraction))
+        .ok_or_else(|| anyhow!("Invalid {key} `{value}`, expected a number from 0 to 1"))
+}
+
+/// Set `key` to `value` in the config file at `path`, creating it if needed.
+pub fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+    if key == "tui.default_sort" {
+        parse_sort(value)?;
+    }
+    if key == "trash.retention" {
+        parse_retention(value)?;
+    }
+    if key == "list.default_sort" {
+        parse_sorts(value)?;
+    }
+    if key == "display.flags" {
+        parse_flags(value)?;
+    }
+    if key == "scan.max_skipped" {
+        parse_fraction(key, value)?;
+    }
+    update_file(path, |file| {
+        match key {
+            "trash.auto_empty" => file.trash.auto_empty = Some(parse_bool(key, value)?),
+            "display.color" => file.display.color = Some(parse_bool(key, value)?),
+            _ => *file.field(key)? = Some(value.to_string()),
+        }
+        Ok(())
+    })
+}
+
+/// Apply `modify` to the config file at `path` under the lock, creating the file if needed.
+pub fn update_file(path: &Path, modify: impl FnOnce(&mut ConfigFile) -> Result<()>) -> Result<()> {
+    let _lock = atomic::lock()?;
+    let mut file = load_file(path)?;
+    modify(&mut file)?;
+    atomic::write(path, toml::to_string(&file)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn a_full_config_survives_a_load_and_save() {
+        let dir = TempDir::new();
+        let path = dir.path().join("config.toml");
+        let mut file = ConfigFile::default();
+        for key in KEYS {
+            if let Ok(field) = file.field(key) {
+                *field = Some(format!("value of {key}"));
+            }
+        }
+        file.trash.auto_empty = Some(true);
+        file.display.color = Some(false);
+        file.kinds = vec![KindRule {
+            kind: "ruby".to_string(),
+            file: Some("Gemfile".to_string()),
+            extension: None,
+            majority: false,
+        }];
+        file.health = vec![HealthCheck {
+            name: "Changelog".to_string(),
+            flag: 'C',
+            files: vec!["CHANGELOG.md".to_string()],
+        }];
+        let saved = toml::to_string(&file).unwrap();
+        atomic::write(&path, &saved).unwrap();
+
+        let mut loaded = load_file(&path).unwrap();
+        assert_eq!(toml::to_string(&loaded).unwrap(), saved);
+        for key in KEYS {
+            if let Ok(field) = loaded.field(key) {
+                assert_eq!(field.as_deref(), Some(format!("value of {key}").as_str()));
+            }
+        }
+        assert_eq!(loaded.trash.auto_empty, Some(true));
+        assert_eq!(loaded.display.color, Some(false));
+        assert_eq!(loaded.kinds, file.kinds);
+        assert_eq!(loaded.health[0].flag, 'C');
+    }
+
+    #[test]
+    fn a_missing_file_saves_and_loads_as_empty() {
+        let dir = TempDir::new();
+        let path = dir.path().join("config.toml");
+        let saved = toml::to_string(&load_file(&path).unwrap()).unwrap();
+        atomic::write(&path, &saved).unwrap();
+        let loaded = load_file(&path).unwrap();
+        assert_eq!(toml::to_string(&loaded).unwrap(), saved);
+        assert!(loaded.editor.command.is_none() && loaded.trash.auto_empty.is_none());
+        assert!(loaded.kinds.is_empty() && loaded.health.is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+use chrono::Local;
+use color_eyre::eyre::{anyhow, Result};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::VpmError,
+    format_name, fsops,
+    meta::{self, ProjectMeta},
+    next_id, template, Project, Status,
+};
+
+/// Everything `vpm new` is going to do, checked before anything is written.
+#[derive(Debug, Clone)]
+pub struct NewPlan {
+    pub project: Project,
+    /// The template name and the directory it is copied from.
+    pub template: Option<(String, PathBuf)>,
+    /// Metadata to write with the project, on top of what the template brings.
+    pub meta: ProjectMeta,
+    /// Use the folder already at the project's path instead of creating one.
+    pub adopt: bool,
+}
+
+/// What to do when the new project's folder already exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    Rename(String),
+    Suffix,
+    Adopt,
+    Abort,
+}
+
+/// Validate the name, allocate an id, resolve the template and check the destination is free.
+pub fn plan_new(
+    projects: &BTreeMap<usize, Project>,
+    root: &str,
+    name: &str,
+    template: Option<&str>,
+    priority: Option<u8>,
+) -> Result<NewPlan> {
+    let plan = draft(projects, root, name, template, priority)?;
+    let path = plan.project.path_in(root);
+    if fs::symlink_metadata(&path).is_ok() {
+        return Err(VpmError::PathExists { path }.into());
+    }
+    Ok(plan)
+}
+
+/// Plan `name` with the first `-2`, `-3`, ... suffix whose folder is free.
+pub fn plan_suffixed(
+    projects: &BTreeMap<usize, Project>,
+    root: &str,
+    name: &str,
+    template: Option<&str>,
+    priority: Option<u8>,
+) -> Result<NewPlan> {
+    for n in 2.. {
+        match plan_new(projects, root, &format!("{name}-{n}"), template, priority) {
+            Err(err) if matches!(err.downcast_ref(), Some(VpmError::PathExists { .. })) => {}
+            plan => return plan,
+        }
+    }
+    unreachable!("ran out of suffixes")
+}
+
+/// Plan to take over the existing folder the project `name` would be created in. The template
+/// is not applied, and folders vpm already knows as a project are refused.
+pub fn plan_adopt(
+    projects: &BTreeMap<usize, Project>,
+    root: &str,
+    name: &str,
+    priority: Option<u8>,
+) -> Result<NewPlan> {
+    let mut plan = draft(projects, root, name, None, priority)?;
+    let path = plan.project.path_in(root);
+    if !Path::new(&path).is_dir() {
+        return Err(anyhow!("{path} is not a folder, it can't be adopted"));
+    }
+    if let Some(existing) = projects
+        .values()
+        .find(|project| project.path_in(root) == path)
+    {
+        return Err(anyhow!(
+            "{path} is already project {}, it can't be adopted",
+            existing.id
+        ));
+    }
+    plan.adopt = true;
+    Ok(plan)
+}
+
+fn draft(
+    projects: &BTreeMap<usize, Project>,
+    root: &str,
+    name: &str,
+    template: Option<&str>,
+    priority: Option<u8>,
+) -> Result<NewPlan> {
+    let formatted = format_name(name).map_err(|reason| VpmError::InvalidName {
+        name: name.to_string(),
+        reason,
+    })?;
+    let template = match template {
+        Some(template) => {
+            let path = template::template_path(root, template);
+            if !path.is_dir() {
+                return Err(if path.exists() {
+                    anyhow!("Template {template} is not a folder!")
+                } else {
+                    anyhow!("Template {template} does not exist!")
+                });
+            }
+            Some((template.to_string(), path))
+        }
+        None => None,
+    };
+    let project = Project::new(
+        root,
+        next_id(projects),
+        formatted,
+        Local::now().date_naive(),
+        Local::now(),
+    );
+    let meta = ProjectMeta {
+        created: Some(project.date),
+        template: template.as_ref().map(|(name, _)| name.clone()),
+        priority,
+        ..Default::default()
+    };
+    Ok(NewPlan {
+        project,
+        template,
+        meta,
+        adopt: false,
+    })
+}
+
+/// Create the project described by `plan`, removing it again if any step fails.
+pub fn execute(plan: &NewPlan) -> Result<()> {
+    let path = PathBuf::from(plan.project.get_path());
+    if plan.adopt {
+        return write_meta(&path, &plan.meta);
+    }
+    if let Some(status_dir) = path.parent() {
+        fs::create_dir_all(status_dir)
+            .map_err(|err| anyhow!("Failed to create {}: {err}", status_dir.display()))?;
+    }
+    let created = match &plan.template {
+        Some((_, template_path)) => fsops::copy_dir(template_path, &path),
+        None => fs::create_dir(&path)
+            .map_err(|err| anyhow!("Failed to create {}: {err}", path.display())),
+    }
+    .and_then(|()| write_meta(&path, &plan.meta));
+    if created.is_err() && path.exists() {
+        let _ = fs::remove_dir_all(&path);
+    }
+    created
+}
+
+/// Copy `source` to a new project called `name` with the next ID, created today. Duplicates of
+/// archived projects are active, the others keep the status of their source.
+pub fn duplicate(
+    projects: &BTreeMap<usize, Project>,
+    source: &Project,
+    name: &str,
+) -> Result<Project> {
+    let formatted = format_name(name).map_err(|reason| VpmError::InvalidName {
+        name: name.to_string(),
+        reason,
+    })?;
+    let status = match source.status {
+        Status::Archived => Status::Active,
+        status => status,
+    };
+    let project = Project::new(
+        &source.root,
+        next_id(projects),
+        formatted,
+        Local::now().date_naive(),
+        Local::now(),
+    )
+    .with_status(status);
+    let path = PathBuf::from(project.get_path());
+    if fs::symlink_metadata(&path).is_ok() {
+        return Err(VpmError::PathExists {
+            path: project.get_path(),
+        }
+        .into());
+    }
+    if let Some(status_dir) = path.parent() {
+        fs::create_dir_all(status_dir)?;
+    }
+    let copied = fsops::copy_dir(Path::new(&source.get_path()), &path).and_then(|()| {
+        // The copy is a new project, it doesn't share the creation date or history.
+        match meta::load(&path)? {
+            Some(mut copied) => {
+                copied.created = None;
+                copied.history.clear();
+                meta::save(&path, &copied)
+            }
+            None => Ok(()),
+        }
+    });
+    if copied.is_err() && path.exists() {
+        let _ = fs::remove_dir_all(&path);
+    }
+    copied.map(|()| project)
+}
+
+/// Write `meta` over whatever metadata the template brought, in a single save.
+fn write_meta(path: &Path, meta: &ProjectMeta) -> Result<()> {
+    let mut merged = meta::load(path)?.unwrap_or_default();
+    if meta.created.is_some() {
+        merged.created = meta.created;
+    }
+    if meta.template.is_some() {
+        merged.template = meta.template.clone();
+    }
+    if meta.priority.is_some() {
+        merged.priority = meta.priority;
+    }
+    meta::save(path, &merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{tree, TempDir};
+
+    fn root(dir: &TempDir) -> &str {
+        dir.path().to_str().unwrap()
+    }
+
+    /// The folder today's project `p01-{name}` would get.
+    fn folder(name: &str) -> String {
+        format!("Active/p01-{name}-{}", Local::now().format("%Y-%m-%d"))
+    }
+
+    fn is_path_exists(err: &color_eyre::Report) -> bool {
+        matches!(err.downcast_ref(), Some(VpmError::PathExists { .. }))
+    }
+
+    #[test]
+    fn plans_a_free_folder() {
+        let dir = TempDir::new();
+        let plan = plan_new(&BTreeMap::new(), root(&dir), "App", None, Some(2)).unwrap();
+        assert_eq!(
+            plan.project.path_in(root(&dir)),
+            dir.path().join(folder("app")).to_str().unwrap()
+        );
+        assert!(!plan.adopt);
+        assert_eq!(plan.meta.priority, Some(2));
+        assert!(tree(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn an_existing_folder_or_file_is_a_collision() {
+        let dir = TempDir::new();
+        dir.mkdir(&folder("app"));
+        dir.write(&folder("notes"), "");
+        for name in ["app", "notes"] {
+            let err = plan_new(&BTreeMap::new(), root(&dir), name, None, None).unwrap_err();
+            assert!(is_path_exists(&err), "{name}: {err}");
+        }
+    }
+
+    #[test]
+    fn suffix_skips_taken_suffixes() {
+        let dir = TempDir::new();
+        dir.mkdir(&folder("app"));
+        dir.mkdir(&folder("app-2"));
+        dir.write(&folder("app-3"), "");
+        let plan = plan_suffixed(&BTreeMap::new(), root(&dir), "app", None, None).unwrap();
+        assert_eq!(plan.project.name, "app-4");
+        assert!(!plan.adopt);
+    }
+
+    #[test]
+    fn suffix_keeps_other_errors() {
+        let dir = TempDir::new();
+        dir.mkdir(&folder("app"));
+        let err =
+            plan_suffixed(&BTreeMap::new(), root(&dir), "app", Some("missing"), None).unwrap_err();
+        assert_eq!(err.to_string(), "Template missing does not exist!");
+    }
+
+    #[test]
+    fn adopts_an_existing_folder_without_the_template() {
+        let dir = TempDir::new();
+        dir.write(&format!("{}/README.md", folder("app")), "kept");
+        let plan = plan_adopt(&BTreeMap::new(), root(&dir), "app", None).unwrap();
+        assert!(plan.adopt);
+        assert!(plan.template.is_none() && plan.meta.template.is_none());
+        assert_eq!(plan.meta.created, Some(plan.project.date));
+    }
+
+    #[test]
+    fn adopt_refuses_files_and_missing_folders() {
+        let dir = TempDir::new();
+        dir.write(&folder("notes"), "");
+        for name in ["notes", "missing"] {
+            let err = plan_adopt(&BTreeMap::new(), root(&dir), name, None).unwrap_err();
+            assert!(
+                err.to_string()
+                    .ends_with("is not a folder, it can't be adopted"),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn adopt_refuses_a_known_project() {
+        let dir = TempDir::new();
+        dir.mkdir(&folder("app"));
+        // Only a stale project list can hold a project at the folder the next id would get.
+        let known = Project::new(
+            root(&dir),
+            1,
+            "app",
+            Local::now().date_naive(),
+            Local::now(),
+        );
+        let projects = BTreeMap::from([(0, known)]);
+        let err = plan_adopt(&projects, root(&dir), "app", None).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("is already project 1, it can't be adopted"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use crate::{Args, Status};
+
+/// Whether to print ANSI colours, checking in order: `--no-color`, `$CLICOLOR_FORCE`,
+/// `$NO_COLOR`, `$CLICOLOR`, `display.color` in the config and finally whether stdout is a
+/// terminal.
+pub fn should_use_color(args: &Args, config_color: bool) -> bool {
+    let var = |name| env::var(name).ok();
+    use_color(
+        args.no_color,
+        var("CLICOLOR_FORCE").as_deref(),
+        var("NO_COLOR").as_deref(),
+        var("CLICOLOR").as_deref(),
+        config_color,
+        io::stdout().is_terminal(),
+    )
+}
+
+fn use_color(
+    no_color_flag: bool,
+    clicolor_force: Option<&str>,
+    no_color: Option<&str>,
+    clicolor: Option<&str>,
+    config_color: bool,
+    is_terminal: bool,
+) -> bool {
+    if no_color_flag {
+        return false;
+    }
+    if clicolor_force.is_some_and(|value| !value.is_empty() && value != "0") {
+        return true;
+    }
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+    if clicolor == Some("0") || !config_color {
+        return false;
+    }
+    is_terminal
+}
+
+/// `text` in the colour of `status`. Written out by hand rather than through crossterm, which
+/// drops colours under `$NO_COLOR` even when `$CLICOLOR_FORCE` asks for them.
+pub fn status_color(text: &str, status: Status) -> String {
+    let code = match status {
+        Status::Active => 32,
+        Status::Paused => 33,
+        Status::Archived => 90,
+    };
+    format!("\x1b[{code}m{text}\x1b[0m")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_precedence() {
+        // (--no-color, CLICOLOR_FORCE, NO_COLOR, CLICOLOR, display.color, tty) -> colour
+        let cases = [
+            ((false, None, None, None, true, true), true),
+            ((false, None, None, None, true, false), false),
+            ((true, Some("1"), None, None, true, true), false),
+            ((false, Some("1"), Some("1"), Some("0"), false, false), true),
+            ((false, Some("0"), None, None, true, false), false),
+            ((false, Some(""), None, None, true, true), true),
+            ((false, None, Some("1"), None, true, true), false),
+            ((false, None, Some(""), None, true, true), true),
+            ((false, None, None, Some("0"), true, true), false),
+            ((false, None, None, Some("1"), true, true), true),
+            ((false, None, None, Some("1"), false, true), false),
+            ((false, None, None, None, false, true), false),
+        ];
+        for ((flag, force, no_color, clicolor, config, tty), expected) in cases {
+            assert_eq!(
+                use_color(flag, force, no_color, clicolor, config, tty),
+                expected,
+                "{:?}",
+                (flag, force, no_color, clicolor, config, tty)
+            );
+        }
+    }
+}
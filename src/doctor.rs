@@ -0,0 +1,155 @@
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use std::path::Path;
+
+use crate::{
+    home::{self, LayoutMode},
+    meta::ProjectMeta,
+    Project, Status,
+};
+
+/// A project whose folder date disagrees with the `created` date in its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateMismatch {
+    pub id: usize,
+    pub folder_date: NaiveDate,
+    pub meta_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DateFix {
+    #[value(help = "Rename the project folder to match the metadata")]
+    Folder,
+    #[value(help = "Update the metadata to match the project folder")]
+    Meta,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixAction {
+    RenameFolder { id: usize, date: NaiveDate },
+    UpdateMeta { id: usize, date: NaiveDate },
+}
+
+pub fn date_mismatches<'a>(
+    scanned: impl IntoIterator<Item = (&'a Project, Option<&'a ProjectMeta>)>,
+) -> Vec<DateMismatch> {
+    scanned
+        .into_iter()
+        .filter_map(|(project, meta)| {
+            let meta_date = meta?.created?;
+            (meta_date != project.date).then_some(DateMismatch {
+                id: project.id,
+                folder_date: project.date,
+                meta_date,
+            })
+        })
+        .collect()
+}
+
+pub fn plan_date_fixes(mismatches: &[DateMismatch], fix: DateFix) -> Vec<FixAction> {
+    mismatches
+        .iter()
+        .map(|mismatch| match fix {
+            DateFix::Folder => FixAction::RenameFolder {
+                id: mismatch.id,
+                date: mismatch.meta_date,
+            },
+            DateFix::Meta => FixAction::UpdateMeta {
+                id: mismatch.id,
+                date: mismatch.folder_date,
+            },
+        })
+        .collect()
+}
+
+/// The status folders missing from `root`. A flat project home has none to miss.
+pub fn missing_status_dirs(root: impl AsRef<Path>) -> Vec<Status> {
+    if home::mode() == LayoutMode::Flat {
+        return Vec::new();
+    }
+    [Status::Active, Status::Paused, Status::Archived]
+        .into_iter()
+        .filter(|status| !root.as_ref().join(status.to_string()).is_dir())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn meta(created: Option<NaiveDate>) -> ProjectMeta {
+        ProjectMeta {
+            created,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mismatches_need_a_differing_created_date() {
+        let projects = [1, 2, 3, 4]
+            .map(|id| Project::new("/projects", id, "p", date(2024, 1, id as u32), Local::now()));
+        let metas = [
+            None,
+            Some(meta(None)),
+            Some(meta(Some(date(2024, 1, 3)))),
+            Some(meta(Some(date(2023, 12, 31)))),
+        ];
+        let scanned = projects.iter().zip(metas.iter().map(Option::as_ref));
+        assert_eq!(
+            date_mismatches(scanned),
+            [DateMismatch {
+                id: 4,
+                folder_date: date(2024, 1, 4),
+                meta_date: date(2023, 12, 31),
+            }]
+        );
+    }
+
+    #[test]
+    fn fixes_take_the_date_from_the_other_side() {
+        let mismatches = [
+            DateMismatch {
+                id: 1,
+                folder_date: date(2024, 1, 1),
+                meta_date: date(2023, 1, 1),
+            },
+            DateMismatch {
+                id: 2,
+                folder_date: date(2024, 2, 2),
+                meta_date: date(2023, 2, 2),
+            },
+        ];
+        assert_eq!(
+            plan_date_fixes(&mismatches, DateFix::Folder),
+            [
+                FixAction::RenameFolder {
+                    id: 1,
+                    date: date(2023, 1, 1)
+                },
+                FixAction::RenameFolder {
+                    id: 2,
+                    date: date(2023, 2, 2)
+                },
+            ]
+        );
+        assert_eq!(
+            plan_date_fixes(&mismatches, DateFix::Meta),
+            [
+                FixAction::UpdateMeta {
+                    id: 1,
+                    date: date(2024, 1, 1)
+                },
+                FixAction::UpdateMeta {
+                    id: 2,
+                    date: date(2024, 2, 2)
+                },
+            ]
+        );
+        assert!(plan_date_fixes(&[], DateFix::Meta).is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::{
+    env,
+    io::ErrorKind,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// The editor for text that vpm asks the user to edit, when neither variable is set.
+const DEFAULT_TEXT_EDITOR: &str = "vi";
+
+/// Editors that run inside the terminal, so `vpm code` waits for them and hands over stdio.
+const TERMINAL_EDITORS: &[&str] = &[
+    "vi", "vim", "nvim", "nano", "micro", "hx", "helix", "kak", "emacs", "joe", "mg", "ne",
+];
+
+/// Whether `program` is one of the `TERMINAL_EDITORS`, looking at its file name only.
+pub fn is_terminal_editor(program: &str) -> bool {
+    let name = Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    TERMINAL_EDITORS.contains(&name.as_str())
+}
+
+/// Open `path` with `editor`, a command line like `code --new-window`, passing `extra` before
+/// the path. Terminal editors take over stdio until they exit, GUI editors are left running.
+pub fn open(editor: &str, extra: &[String], path: &str, terminal: bool) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or(anyhow!("The editor command is empty!"))?;
+    let mut command = Command::new(program);
+    command.args(words).args(extra).arg(path);
+    let not_found = |err: std::io::Error| match err.kind() {
+        ErrorKind::NotFound => anyhow!(
+            "Could not find the editor `{program}` to run `{editor}`, \
+             pick another with --editor, $VPM_EDITOR or $EDITOR"
+        ),
+        _ => anyhow!("Failed to run `{editor}`: {err}"),
+    };
+    if terminal || is_terminal_editor(program) {
+        let status = command.status().map_err(not_found)?;
+        if !status.success() {
+            return Err(anyhow!("`{editor}` exited with {status}"));
+        }
+    } else {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(not_found)?;
+    }
+    Ok(())
+}
+
+/// Open the file `path` in `$VISUAL` or `$EDITOR` and wait for the editor to exit. Unlike
+/// `vpm code` this ignores `editor.command`, which is usually a GUI editor that returns at once.
+pub fn edit_file(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_TEXT_EDITOR.to_string());
+    open(&editor, &[], &path.to_string_lossy(), true)
+}
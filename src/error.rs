@@ -0,0 +1,129 @@
+use serde::Serialize;
+use std::{error::Error, fmt::Display};
+
+/// Errors that scripts may want to tell apart, serialized by their variant name under `--json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind")]
+pub enum VpmError {
+    ProjectNotFound {
+        id: usize,
+    },
+    InvalidName {
+        name: String,
+        reason: String,
+    },
+    ProjectHomeNotSet,
+    /// Something that isn't a known project is in the way of a new one.
+    PathExists {
+        path: String,
+    },
+}
+
+impl VpmError {
+    /// The process exit code for the error, 1 unless scripts need to tell it apart.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            VpmError::PathExists { .. } => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl Display for VpmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VpmError::ProjectNotFound { id } => write!(f, "Project {id} not found!"),
+            VpmError::InvalidName { name, reason } => {
+                write!(f, "Invalid project name `{name}`: {reason}")
+            }
+            VpmError::ProjectHomeNotSet => write!(
+                f,
+                "You must set the $PROJECT_HOME variable, `project_home` in the config file or --project-home to the root of your projects folder!"
+            ),
+            VpmError::PathExists { path } => write!(f, "{path} already exists!"),
+        }
+    }
+}
+
+impl Error for VpmError {}
+
+/// Render `err` as `{"schema": 1, "error": {"kind": ..., "message": ...}}`, with the fields of
+/// known variants.
+pub fn to_json(err: &color_eyre::eyre::Report) -> serde_json::Value {
+    let mut error = match err.downcast_ref::<VpmError>() {
+        Some(vpm_error) => serde_json::to_value(vpm_error).unwrap_or_default(),
+        None => serde_json::json!({ "kind": "Other" }),
+    };
+    error["message"] = err.to_string().into();
+    crate::json::versioned(serde_json::json!({ "error": error }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color_eyre::eyre::{anyhow, Report};
+    use serde_json::json;
+
+    #[test]
+    fn exit_codes() {
+        let cases = [
+            (VpmError::ProjectNotFound { id: 1 }, 1),
+            (
+                VpmError::InvalidName {
+                    name: "..".to_string(),
+                    reason: "dots".to_string(),
+                },
+                1,
+            ),
+            (VpmError::ProjectHomeNotSet, 1),
+            (
+                VpmError::PathExists {
+                    path: "/p".to_string(),
+                },
+                3,
+            ),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.exit_code(), code, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn json_shape_per_kind() {
+        let cases = [
+            (
+                Report::new(VpmError::PathExists {
+                    path: "/p/Active/p01-x-2024-01-01".to_string(),
+                }),
+                json!({
+                    "kind": "PathExists",
+                    "path": "/p/Active/p01-x-2024-01-01",
+                    "message": "/p/Active/p01-x-2024-01-01 already exists!",
+                }),
+            ),
+            (
+                Report::new(VpmError::InvalidName {
+                    name: "日本".to_string(),
+                    reason: "no ASCII".to_string(),
+                }),
+                json!({
+                    "kind": "InvalidName",
+                    "name": "日本",
+                    "reason": "no ASCII",
+                    "message": "Invalid project name `日本`: no ASCII",
+                }),
+            ),
+            (
+                Report::new(VpmError::ProjectNotFound { id: 42 }),
+                json!({ "kind": "ProjectNotFound", "id": 42, "message": "Project 42 not found!" }),
+            ),
+            (
+                anyhow!("something broke"),
+                json!({ "kind": "Other", "message": "something broke" }),
+            ),
+        ];
+        for (report, error) in cases {
+            assert_eq!(to_json(&report), json!({ "schema": 1, "error": error }));
+        }
+    }
+}
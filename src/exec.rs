@@ -0,0 +1,209 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::Project;
+
+/// How running the command in one project ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Succeeded,
+    /// The command exited unsuccessfully, with its exit code unless a signal killed it.
+    Failed(Option<i32>),
+    /// The command could not be started.
+    Error(String),
+    /// Not run, because of `--fail-fast` or Ctrl-C.
+    Skipped,
+}
+
+impl Outcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Outcome::Failed(_) | Outcome::Error(_))
+    }
+}
+
+/// The label in front of each output line of a project, e.g. `[p0C foo]`.
+pub fn prefix(project: &Project) -> String {
+    format!("[p{} {}]", project.hex_id(), project.name)
+}
+
+/// Run `command` in each of `projects`, `jobs` at a time, printing their output line by line
+/// behind the project's prefix. No new commands start once `stop` is set, which happens on the
+/// first failure with `fail_fast`. Outcomes are returned in the order of `projects`.
+pub fn run_all(
+    projects: &[&Project],
+    command: &[String],
+    jobs: usize,
+    fail_fast: bool,
+    stop: &AtomicBool,
+) -> Vec<Outcome> {
+    let queue = Mutex::new(projects.iter().enumerate());
+    let outcomes = Mutex::new(vec![Outcome::Skipped; projects.len()]);
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, projects.len().max(1)) {
+            scope.spawn(|| loop {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Some((i, project)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let outcome = run_one(&prefix(project), project.get_path(), command);
+                if fail_fast && outcome.is_failure() {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                outcomes.lock().unwrap()[i] = outcome;
+            });
+        }
+    });
+    outcomes.into_inner().unwrap()
+}
+
+fn run_one(prefix: &str, path: impl AsRef<Path>, command: &[String]) -> Outcome {
+    let Some((program, args)) = command.split_first() else {
+        return Outcome::Error("No command given".to_string());
+    };
+    let child = Command::new(program)
+        .args(args)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => return Outcome::Error(format!("Failed to run `{program}`: {err}")),
+    };
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| forward(stdout, prefix, io::stdout()));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| forward(stderr, prefix, io::stderr()));
+        }
+    });
+    match child.wait() {
+        Ok(status) if status.success() => Outcome::Succeeded,
+        Ok(status) => Outcome::Failed(status.code()),
+        Err(err) => Outcome::Error(err.to_string()),
+    }
+}
+
+/// Write every line of `output` behind `prefix` to `to`, whole lines at a time so that parallel
+/// jobs interleave by line.
+fn forward(output: impl Read, prefix: &str, mut to: impl Write) {
+    let mut reader = BufReader::new(output);
+    let mut line = Vec::new();
+    while reader
+        .read_until(b'\n', &mut line)
+        .is_ok_and(|read| read > 0)
+    {
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if writeln!(to, "{prefix} {text}").is_err() {
+            break;
+        }
+        line.clear();
+    }
+}
+
+/// One line per project with its outcome, followed by the totals.
+pub fn summary(projects: &[&Project], outcomes: &[Outcome]) -> Vec<String> {
+    let mut lines = projects
+        .iter()
+        .zip(outcomes)
+        .map(|(project, outcome)| {
+            let (label, detail) = match outcome {
+                Outcome::Succeeded => ("ok", String::new()),
+                Outcome::Failed(Some(code)) => ("failed", format!(" (exit {code})")),
+                Outcome::Failed(None) => ("failed", " (killed by a signal)".to_string()),
+                Outcome::Error(err) => ("error", format!(" ({err})")),
+                Outcome::Skipped => ("skipped", String::new()),
+            };
+            format!("{label:8} {}{detail}", project.full_name())
+        })
+        .collect::<Vec<_>>();
+    let count = |matches: fn(&Outcome) -> bool| outcomes.iter().filter(|o| matches(o)).count();
+    lines.push(format!(
+        "{} succeeded, {} failed, {} skipped",
+        count(|outcome| *outcome == Outcome::Succeeded),
+        count(Outcome::is_failure),
+        count(|outcome| *outcome == Outcome::Skipped),
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, NaiveDate};
+
+    fn project(id: usize, name: &str) -> Project {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        Project::new("/projects", id, name, date, Local::now())
+    }
+
+    fn forwarded(output: &[u8]) -> String {
+        let mut to = Vec::new();
+        forward(output, "[p0C foo]", &mut to);
+        String::from_utf8(to).unwrap()
+    }
+
+    #[test]
+    fn prefixes_name_the_project_by_hex_id() {
+        assert_eq!(prefix(&project(12, "foo")), "[p0C foo]");
+        assert_eq!(prefix(&project(300, "bar-baz")), "[p12C bar-baz]");
+    }
+
+    #[test]
+    fn forwards_every_line_behind_the_prefix() {
+        assert_eq!(
+            forwarded(b"one\ntwo\r\n\nlast without newline"),
+            "[p0C foo] one\n[p0C foo] two\n[p0C foo] \n[p0C foo] last without newline\n"
+        );
+        assert_eq!(forwarded(b""), "");
+        assert_eq!(
+            forwarded(b"bad \xff byte\n"),
+            "[p0C foo] bad \u{fffd} byte\n"
+        );
+    }
+
+    #[test]
+    fn summarizes_each_outcome_and_the_totals() {
+        let projects = [
+            project(1, "ok"),
+            project(2, "exit"),
+            project(3, "signal"),
+            project(4, "missing"),
+            project(5, "later"),
+        ];
+        let projects = projects.iter().collect::<Vec<_>>();
+        let outcomes = [
+            Outcome::Succeeded,
+            Outcome::Failed(Some(2)),
+            Outcome::Failed(None),
+            Outcome::Error("Failed to run `nope`".to_string()),
+            Outcome::Skipped,
+        ];
+        assert_eq!(
+            summary(&projects, &outcomes),
+            [
+                "ok       p01-ok-2024-01-15",
+                "failed   p02-exit-2024-01-15 (exit 2)",
+                "failed   p03-signal-2024-01-15 (killed by a signal)",
+                "error    p04-missing-2024-01-15 (Failed to run `nope`)",
+                "skipped  p05-later-2024-01-15",
+                "1 succeeded, 3 failed, 1 skipped",
+            ]
+        );
+        assert_eq!(summary(&[], &[]), ["0 succeeded, 0 failed, 0 skipped"]);
+    }
+}
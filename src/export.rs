@@ -0,0 +1,355 @@
+use chrono::{Local, NaiveDate};
+use color_eyre::eyre::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    fsops::ScratchDir,
+    paths::Paths,
+    tombstone::{self, Tombstone},
+    trash, Project, Status,
+};
+
+const MANIFEST_FILENAME: &str = "vpm-manifest.toml";
+pub const ARCHIVE_EXTENSION: &str = "tar.zst";
+
+/// Describes an exported project and the checksum of every file in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub id: usize,
+    pub name: String,
+    pub date: NaiveDate,
+    pub status: Status,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// What to do with the local copy once a project was archived elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalAction {
+    Keep,
+    Trash,
+    Delete,
+}
+
+pub fn sha256(path: impl AsRef<Path>) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Every regular file under `root`, relative to it, without following symlinks.
+fn files(root: &Path, dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            files.extend(self::files(root, &entry.path())?);
+        } else if file_type.is_file() {
+            files.push(entry.path().strip_prefix(root)?.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// Size and checksum of every file under `root`, sorted by path.
+pub fn manifest_files(root: &Path) -> Result<Vec<ManifestFile>> {
+    let mut files = files(root, root)?;
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let full_path = root.join(&path);
+            Ok(ManifestFile {
+                size: fs::metadata(&full_path)?.len(),
+                sha256: sha256(&full_path)?,
+                path: path.to_string_lossy().into_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Check that the files under `root` match the sizes and checksums in `files`.
+pub fn verify(root: &Path, files: &[ManifestFile]) -> Result<()> {
+    for file in files {
+        let path = root.join(&file.path);
+        let size = fs::metadata(&path)
+            .map_err(|err| anyhow!("{} is missing from the archive: {err}", file.path))?
+            .len();
+        if size != file.size || sha256(&path)? != file.sha256 {
+            return Err(anyhow!("Checksum mismatch for {}", file.path));
+        }
+    }
+    Ok(())
+}
+
+pub fn manifest(project: &Project, dir: &Path) -> Result<Manifest> {
+    Ok(Manifest {
+        id: project.id,
+        name: project.name.clone(),
+        date: project.date,
+        status: project.status,
+        files: manifest_files(dir)?,
+    })
+}
+
+/// Write `project`, whose folder is `dir`, to a zstd-compressed tarball at `archive_path`, with
+/// a manifest at its root.
+pub fn write_archive(project: &Project, dir: &Path, archive_path: &Path) -> Result<()> {
+    let manifest = toml::to_string(&manifest(project, dir)?)?;
+    write_tarball(archive_path, &manifest, &project.full_name(), dir)
+}
+
+/// Write `dir` to a zstd-compressed tarball at `archive_path` under the name `dir_name`,
+/// with `manifest` at its root.
+pub fn write_tarball(
+    archive_path: &Path,
+    manifest: &str,
+    dir_name: &str,
+    dir: &Path,
+) -> Result<()> {
+    let encoder = zstd::Encoder::new(File::create(archive_path)?, 0)?;
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Local::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_FILENAME, manifest.as_bytes())?;
+    builder.append_dir_all(dir_name, dir)?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Unpack a tarball written by [`write_tarball`] into the directory `into` and return its manifest.
+pub fn unpack_tarball(archive_path: &Path, into: &Path) -> Result<String> {
+    let decoder = zstd::Decoder::new(File::open(archive_path)?)?;
+    tar::Archive::new(decoder)
+        .unpack(into)
+        .map_err(|err| anyhow!("Could not unpack {}: {err}", archive_path.display()))?;
+    let manifest_path = into.join(MANIFEST_FILENAME);
+    let manifest = fs::read_to_string(&manifest_path).map_err(|err| {
+        anyhow!(
+            "{} has no {MANIFEST_FILENAME}: {err}",
+            archive_path.display()
+        )
+    })?;
+    fs::remove_file(manifest_path)?;
+    Ok(manifest)
+}
+
+/// The steps of [`archive_to`], in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Stage,
+    Copy,
+    Verify,
+    Tombstone,
+    Local,
+}
+
+/// Export `project` into the directory `to`, verify the copy, record a tombstone and then
+/// handle the local directory. The local project is left untouched if any step before that fails,
+/// and so is `to` if the export isn't recorded.
+pub fn archive_to(
+    project: &Project,
+    to: &Path,
+    local: LocalAction,
+    message: Option<String>,
+    paths: &Paths,
+) -> Result<PathBuf> {
+    archive_with(project, to, local, message, paths, |_| Ok(()))
+}
+
+/// [`archive_to`], calling `before` ahead of each step so tests can fail any of them.
+fn archive_with(
+    project: &Project,
+    to: &Path,
+    local: LocalAction,
+    message: Option<String>,
+    paths: &Paths,
+    before: impl Fn(Step) -> Result<()>,
+) -> Result<PathBuf> {
+    let source = PathBuf::from(project.get_path());
+    let file_name = format!("{}.{ARCHIVE_EXTENSION}", project.full_name());
+    let destination = to.join(&file_name);
+    let checksum_file = to.join(format!("{file_name}.sha256"));
+    if destination.exists() {
+        return Err(anyhow!("{} already exists!", destination.display()));
+    }
+    fs::create_dir_all(to)?;
+
+    // Until the tombstone is recorded a failure removes any partial copy, so the export can be
+    // retried.
+    let exported = (|| {
+        before(Step::Stage)?;
+        let staging = ScratchDir::new("vpm-archive")?;
+        let staged = staging.path().join(&file_name);
+        write_archive(project, &source, &staged)?;
+        let checksum = sha256(&staged)?;
+        before(Step::Copy)?;
+        fs::copy(&staged, &destination)
+            .map_err(|err| anyhow!("Failed to copy archive to {}: {err}", destination.display()))?;
+        before(Step::Verify)?;
+        if sha256(&destination)? != checksum {
+            return Err(anyhow!(
+                "Checksum mismatch after copying to {}",
+                destination.display()
+            ));
+        }
+        fs::write(&checksum_file, format!("{checksum}  {file_name}\n"))?;
+        before(Step::Tombstone)?;
+        tombstone::record(
+            paths,
+            Tombstone {
+                id: project.id,
+                full_name: project.full_name(),
+                destination: destination.clone(),
+                date: Local::now().date_naive(),
+                sha256: checksum.clone(),
+                message,
+            },
+        )
+    })();
+    if let Err(err) = exported {
+        let _ = fs::remove_file(&destination);
+        let _ = fs::remove_file(&checksum_file);
+        return Err(anyhow!("{err}, the local project was kept"));
+    }
+
+    let handled = before(Step::Local).and_then(|()| match local {
+        LocalAction::Keep => Ok(()),
+        LocalAction::Trash => trash::trash(&project.root, &source).map(|_| ()),
+        LocalAction::Delete => fs::remove_dir_all(&source).map_err(Into::into),
+    });
+    handled.map_err(|err| {
+        anyhow!(
+            "Exported to {}, but the local project was kept: {err}",
+            destination.display()
+        )
+    })?;
+    Ok(destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{tree, TempDir};
+
+    struct Setup {
+        dir: TempDir,
+        project: Project,
+        paths: Paths,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let dir = TempDir::new();
+            let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+            let root = dir.path().join("root");
+            let project = Project::new(root.to_string_lossy(), 1, "app", date, Local::now());
+            dir.write(
+                "root/Active/p01-app-2024-01-01/src/main.rs",
+                "fn main() {}\n",
+            );
+            dir.write("root/Active/p01-app-2024-01-01/README.md", "# app\n");
+            let paths = Paths {
+                config_dir: dir.path().join("config"),
+                cache_dir: dir.path().join("cache"),
+                state_dir: dir.path().join("state"),
+            };
+            Self {
+                dir,
+                project,
+                paths,
+            }
+        }
+
+        fn to(&self) -> PathBuf {
+            self.dir.path().join("exports")
+        }
+
+        fn archive(&self, fail: Option<Step>) -> Result<PathBuf> {
+            archive_with(
+                &self.project,
+                &self.to(),
+                LocalAction::Trash,
+                None,
+                &self.paths,
+                |step| match fail == Some(step) {
+                    true => Err(anyhow!("{step:?} failed")),
+                    false => Ok(()),
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn archiving_exports_records_and_trashes() {
+        let setup = Setup::new();
+        let destination = setup.archive(None).unwrap();
+        assert_eq!(destination, setup.to().join("p01-app-2024-01-01.tar.zst"));
+        assert_eq!(
+            tree(&setup.to()),
+            [
+                "p01-app-2024-01-01.tar.zst",
+                "p01-app-2024-01-01.tar.zst.sha256"
+            ]
+        );
+        assert!(!Path::new(&setup.project.get_path()).exists());
+        let tombstone = tombstone::find(&setup.paths, 1).unwrap().unwrap();
+        assert_eq!(tombstone.destination, destination);
+        assert_eq!(tombstone.sha256, sha256(&destination).unwrap());
+    }
+
+    #[test]
+    fn a_failing_step_keeps_the_local_project() {
+        for step in [
+            Step::Stage,
+            Step::Copy,
+            Step::Verify,
+            Step::Tombstone,
+            Step::Local,
+        ] {
+            let setup = Setup::new();
+            let local = PathBuf::from(setup.project.get_path());
+            let before = tree(&local);
+            let err = setup.archive(Some(step)).unwrap_err();
+            assert!(err.to_string().contains("local project was kept"), "{err}");
+            assert_eq!(tree(&local), before, "{step:?}");
+            if step == Step::Local {
+                // The export itself went through, so it stays recorded.
+                assert_eq!(tree(&setup.to()).len(), 2, "{step:?}");
+                assert!(tombstone::find(&setup.paths, 1).unwrap().is_some());
+            } else {
+                assert!(tree(&setup.to()).is_empty(), "{step:?}");
+                assert!(tombstone::find(&setup.paths, 1).unwrap().is_none());
+            }
+        }
+    }
+}
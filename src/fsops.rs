@@ -0,0 +1,166 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::{
+    env,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A new folder under the system temp dir that only the current user can open, removed again
+/// with everything in it when this is dropped.
+#[derive(Debug)]
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// Create a folder named `{prefix}-...` that didn't exist before, so nothing another user
+    /// put in the temp dir can be followed or reused.
+    pub fn new(prefix: &str) -> io::Result<Self> {
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos());
+        for attempt in 0u32.. {
+            let path = env::temp_dir().join(format!(
+                "{prefix}-{}-{nanos:x}-{attempt}",
+                std::process::id()
+            ));
+            match builder.create(&path) {
+                Ok(()) => return Ok(Self(path)),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists && attempt < 100 => {}
+                Err(err) => return Err(err),
+            }
+        }
+        unreachable!("gave up after 100 attempts")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Create the new file `name` in this folder holding `contents`, failing if it exists.
+    pub fn create_file(&self, name: &str, contents: &str) -> io::Result<PathBuf> {
+        let path = self.0.join(name);
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?
+            .write_all(contents.as_bytes())?;
+        Ok(path)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Recursively copy `from` to `to`, recreating symlinks instead of following them.
+pub fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir(to).map_err(|err| anyhow!("Failed to create {}: {err}", to.display()))?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let (source, destination) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&source, &destination)?;
+        } else if file_type.is_dir() {
+            copy_dir(&source, &destination)?;
+        } else {
+            fs::copy(&source, &destination).map_err(|err| {
+                anyhow!(
+                    "Failed to copy {} to {}: {err}",
+                    source.display(),
+                    destination.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, destination: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, destination)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, _destination: &Path) -> Result<()> {
+    eprintln!("Warning: skipping symlink {}", source.display());
+    Ok(())
+}
+
+/// Move the folder `from` to `to`, copying and deleting it when they are on different file
+/// systems.
+pub fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+            copy_dir(from, to)?;
+            fs::remove_dir_all(from)
+                .map_err(|err| anyhow!("Copied but failed to remove {}: {err}", from.display()))
+        }
+        result => result.map_err(|err| {
+            anyhow!(
+                "Failed to move {} to {}: {err}",
+                from.display(),
+                to.display()
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{tree, TempDir};
+
+    #[test]
+    fn copies_nested_folders_and_files() {
+        let dir = TempDir::new();
+        dir.write("from/README.md", "hello");
+        dir.write("from/src/bin/main.rs", "fn main() {}");
+        dir.mkdir("from/empty");
+        copy_dir(&dir.path().join("from"), &dir.path().join("to")).unwrap();
+        assert_eq!(tree(&dir.path().join("to")), tree(&dir.path().join("from")));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("to/src/bin/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn recreates_symlinks_without_following_them() {
+        let dir = TempDir::new();
+        dir.write("from/file", "contents");
+        // Following either of these would recurse forever.
+        std::os::unix::fs::symlink("..", dir.path().join("from/parent")).unwrap();
+        std::os::unix::fs::symlink(".", dir.path().join("from/itself")).unwrap();
+        std::os::unix::fs::symlink("missing", dir.path().join("from/dangling")).unwrap();
+        copy_dir(&dir.path().join("from"), &dir.path().join("to")).unwrap();
+        assert_eq!(
+            tree(&dir.path().join("to")),
+            ["dangling", "file", "itself", "parent"]
+        );
+        for (link, target) in [("parent", ".."), ("itself", "."), ("dangling", "missing")] {
+            let copied = fs::read_link(dir.path().join("to").join(link)).unwrap();
+            assert_eq!(copied, Path::new(target));
+        }
+    }
+
+    #[test]
+    fn refuses_to_overwrite() {
+        let dir = TempDir::new();
+        dir.write("from/file", "new");
+        dir.write("to/file", "old");
+        assert!(copy_dir(&dir.path().join("from"), &dir.path().join("to")).is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("to/file")).unwrap(),
+            "old"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::{io::ErrorKind, path::Path, process::Command};
+
+/// Run `git` with `args` in `path`, failing with git's own message when it does.
+fn git(path: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .map_err(|err| match err.kind() {
+            ErrorKind::NotFound => anyhow!("git is not on your PATH, install it first"),
+            _ => anyhow!("Failed to run git: {err}"),
+        })?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Create a repository in `path`, from the git template directory `template` if given.
+pub fn init(path: &Path, template: Option<&Path>) -> Result<()> {
+    match template {
+        Some(template) => git(
+            path,
+            &["init", "--quiet", "--template", &template.to_string_lossy()],
+        ),
+        None => git(path, &["init", "--quiet"]),
+    }
+}
+
+/// Stage everything in `path` and commit it, even when there is nothing to stage.
+pub fn initial_commit(path: &Path) -> Result<()> {
+    git(path, &["add", "."])?;
+    git(
+        path,
+        &["commit", "--quiet", "--allow-empty", "-m", "Initial commit"],
+    )
+}
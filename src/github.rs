@@ -0,0 +1,248 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use color_eyre::eyre::{anyhow, Result};
+use serde::Deserialize;
+use std::{collections::HashSet, env, process::Command};
+
+const API_ROOT: &str = "https://api.github.com";
+const PER_PAGE: usize = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Repo {
+    pub name: String,
+    pub clone_url: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+impl Repo {
+    pub fn created(&self) -> NaiveDate {
+        self.created_at.date_naive()
+    }
+}
+
+/// Fetch every public repository of `user`, authenticating with `$GITHUB_TOKEN` when it is set.
+pub fn list_repos(user: &str) -> Result<Vec<Repo>> {
+    let token = env::var("GITHUB_TOKEN").ok();
+    let mut repos = Vec::new();
+    for page in 1.. {
+        let url = format!("{API_ROOT}/users/{user}/repos?per_page={PER_PAGE}&page={page}");
+        let mut request = ureq::get(&url)
+            .set("User-Agent", "vpm")
+            .set("Accept", "application/vnd.github+json");
+        if let Some(token) = &token {
+            request = request.set("Authorization", &format!("Bearer {token}"));
+        }
+        let page_repos: Vec<Repo> = match request.call() {
+            Ok(response) => response.into_json()?,
+            Err(ureq::Error::Status(403 | 429, response))
+                if response.header("x-ratelimit-remaining") == Some("0") =>
+            {
+                let reset = response
+                    .header("x-ratelimit-reset")
+                    .and_then(|reset| reset.parse().ok())
+                    .and_then(|reset| DateTime::<Utc>::from_timestamp(reset, 0))
+                    .map(|reset| format!(" until {reset}"))
+                    .unwrap_or_default();
+                return Err(anyhow!(
+                    "GitHub rate limit exceeded{reset}, set $GITHUB_TOKEN to raise the limit"
+                ));
+            }
+            Err(ureq::Error::Status(404, _)) => {
+                return Err(anyhow!("GitHub user `{user}` not found!"))
+            }
+            Err(err) => return Err(anyhow!("Failed to fetch repositories from GitHub: {err}")),
+        };
+        let done = page_repos.len() < PER_PAGE;
+        repos.extend(page_repos);
+        if done {
+            break;
+        }
+    }
+    Ok(repos)
+}
+
+/// Match `name` against a pattern where `*` matches any run of characters.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.to_lowercase(), name.to_lowercase());
+    let parts = pattern.split('*').collect::<Vec<_>>();
+    if parts.len() == 1 {
+        return name.contains(&pattern);
+    }
+    let mut rest = name.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(stripped) => rest = stripped,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+pub fn filter_repos(
+    repos: Vec<Repo>,
+    topic: Option<&str>,
+    include: Option<&str>,
+    exclude: Option<&str>,
+) -> Vec<Repo> {
+    repos
+        .into_iter()
+        .filter(|repo| topic.is_none_or(|topic| repo.topics.iter().any(|t| t == topic)))
+        .filter(|repo| include.is_none_or(|pattern| matches_pattern(pattern, &repo.name)))
+        .filter(|repo| exclude.is_none_or(|pattern| !matches_pattern(pattern, &repo.name)))
+        .collect()
+}
+
+/// Reduce a git remote URL to `host/owner/repo` so https and ssh remotes compare equal.
+pub fn normalize_remote(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let url = url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or_else(|| url.strip_prefix("git@").unwrap_or(url));
+    let url = url.rsplit_once('@').map(|(_, rest)| rest).unwrap_or(url);
+    url.replacen(':', "/", 1).to_lowercase()
+}
+
+/// Collect the normalized `origin` remotes of the given project directories.
+pub fn existing_remotes<'a>(paths: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            Command::new("git")
+                .args(["-C", path, "remote", "get-url", "origin"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| normalize_remote(&String::from_utf8_lossy(&output.stdout)))
+        })
+        .collect()
+}
+
+pub fn clone(repo: &Repo, path: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["clone", "--quiet", &repo.clone_url, path])
+        .output()
+        .map_err(|err| anyhow!("Failed to run git: {err}"))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git clone {} failed: {}",
+            repo.clone_url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(name: &str, topics: &[&str]) -> Repo {
+        Repo {
+            name: name.to_string(),
+            clone_url: format!("https://github.com/me/{name}.git"),
+            html_url: format!("https://github.com/me/{name}"),
+            description: None,
+            created_at: DateTime::UNIX_EPOCH,
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+        }
+    }
+
+    fn names(repos: &[Repo]) -> Vec<&str> {
+        repos.iter().map(|repo| repo.name.as_str()).collect()
+    }
+
+    #[test]
+    fn patterns_without_a_wildcard_match_anywhere() {
+        assert!(matches_pattern("cli", "my-cli-tool"));
+        assert!(matches_pattern("CLI", "my-cli-tool"));
+        assert!(!matches_pattern("cli", "my-tool"));
+    }
+
+    #[test]
+    fn wildcards_anchor_the_rest_of_the_pattern() {
+        for (pattern, name, matches) in [
+            ("*-rs", "parser-rs", true),
+            ("*-rs", "parser-rs-old", false),
+            ("rust-*", "rust-parser", true),
+            ("rust-*", "old-rust-parser", false),
+            ("rust-*-cli", "rust-todo-cli", true),
+            ("rust-*-cli", "rust-cli", false),
+            ("rust-*-cli", "rust-todo-cli-old", false),
+            ("*todo*", "rust-todo-cli", true),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "axxcyyb", false),
+            ("a*a", "a", false),
+            ("*", "anything", true),
+        ] {
+            assert_eq!(matches_pattern(pattern, name), matches, "{pattern} {name}");
+        }
+    }
+
+    #[test]
+    fn repos_filter_by_topic_include_and_exclude() {
+        let repos = || {
+            vec![
+                repo("rust-cli", &["rust", "cli"]),
+                repo("rust-web", &["rust"]),
+                repo("dotfiles", &[]),
+            ]
+        };
+        assert_eq!(names(&filter_repos(repos(), None, None, None)).len(), 3);
+        assert_eq!(
+            names(&filter_repos(repos(), Some("rust"), None, None)),
+            ["rust-cli", "rust-web"]
+        );
+        assert_eq!(
+            names(&filter_repos(repos(), None, Some("rust-*"), None)),
+            ["rust-cli", "rust-web"]
+        );
+        assert_eq!(
+            names(&filter_repos(repos(), None, None, Some("*-web"))),
+            ["rust-cli", "dotfiles"]
+        );
+        assert_eq!(
+            names(&filter_repos(
+                repos(),
+                Some("rust"),
+                Some("rust-*"),
+                Some("*-cli")
+            )),
+            ["rust-web"]
+        );
+        assert!(filter_repos(repos(), Some("RUST"), None, None).is_empty());
+    }
+
+    #[test]
+    fn remotes_normalize_to_host_owner_repo() {
+        for url in [
+            "https://github.com/Me/App.git",
+            "https://github.com/me/app",
+            "https://github.com/me/app/\n",
+            "https://token@github.com/me/app.git",
+            "git@github.com:me/app.git",
+            "ssh://git@github.com/me/app.git",
+            "ssh://someone@github.com/me/app",
+        ] {
+            assert_eq!(normalize_remote(url), "github.com/me/app", "{url}");
+        }
+        assert_ne!(
+            normalize_remote("git@gitlab.com:me/app.git"),
+            normalize_remote("git@github.com:me/app.git")
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{index, meta, Project};
+
+const MISSING_FLAG: char = '-';
+
+/// A health check passing when any of `files` exists in the project's top level.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub flag: char,
+    pub files: Vec<String>,
+}
+
+impl HealthCheck {
+    fn new(name: &str, flag: char, files: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            flag,
+            files: files.iter().map(|file| file.to_string()).collect(),
+        }
+    }
+}
+
+pub fn default_checks() -> Vec<HealthCheck> {
+    vec![
+        HealthCheck::new(
+            "readme",
+            'R',
+            &[
+                "README.md",
+                "README",
+                "README.txt",
+                "README.rst",
+                "readme.md",
+            ],
+        ),
+        HealthCheck::new(
+            "license",
+            'L',
+            &[
+                "LICENSE",
+                "LICENSE.md",
+                "LICENSE.txt",
+                "LICENCE",
+                "COPYING",
+                "LICENSE-MIT",
+                "LICENSE-APACHE",
+            ],
+        ),
+        HealthCheck::new("git", 'G', &[".git"]),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub flag: char,
+    pub passed: bool,
+}
+
+/// The outcome of every health check for one project, in display order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Health {
+    pub results: Vec<CheckResult>,
+}
+
+impl Health {
+    /// One character per check: its flag if it passed, `-` otherwise.
+    pub fn flags(&self) -> String {
+        self.results
+            .iter()
+            .map(|result| {
+                if result.passed {
+                    result.flag
+                } else {
+                    MISSING_FLAG
+                }
+            })
+            .collect()
+    }
+}
+
+fn has_remote(path: &Path) -> bool {
+    fs::read_to_string(path.join(".git").join("config"))
+        .is_ok_and(|config| config.contains("[remote \""))
+}
+
+/// Run `checks` on the project at `path`, reusing the `cached` file check results. Returns the
+/// project's health and the file check results that weren't cached.
+fn check(
+    path: &Path,
+    checks: &[HealthCheck],
+    cached: Option<&BTreeMap<String, bool>>,
+) -> Result<(Health, BTreeMap<String, bool>)> {
+    let mut fresh = BTreeMap::new();
+    let mut results = checks
+        .iter()
+        .map(|check| {
+            let cached = cached.and_then(|cached| cached.get(&check.name).copied());
+            let passed = cached.unwrap_or_else(|| {
+                let passed = check.files.iter().any(|file| path.join(file).exists());
+                fresh.insert(check.name.clone(), passed);
+                passed
+            });
+            CheckResult {
+                name: check.name.clone(),
+                flag: check.flag,
+                passed,
+            }
+        })
+        .collect::<Vec<_>>();
+    let described =
+        meta::load(path)?.is_some_and(|meta| !meta.description.is_empty() || !meta.tags.is_empty());
+    let live = [
+        ("remote", 'O', has_remote(path)),
+        ("description", 'D', described),
+    ];
+    // Keep the built-in checks together, ahead of the ones from the config.
+    let position = results.len().min(default_checks().len());
+    results.splice(
+        position..position,
+        live.map(|(name, flag, passed)| CheckResult {
+            name: name.to_string(),
+            flag,
+            passed,
+        }),
+    );
+    Ok((Health { results }, fresh))
+}
+
+/// Run `checks` on every project. File checks are cached in the index by directory mtime,
+/// the remote and description checks read their files every time.
+pub fn resolve_health(
+    projects: &mut BTreeMap<usize, Project>,
+    checks: &[HealthCheck],
+) -> Result<()> {
+    let index = index::load();
+    let mut computed = Vec::new();
+    for project in projects.values_mut() {
+        let path = project.get_path();
+        let Some(mtime) = index::mtime(&path) else {
+            continue;
+        };
+        let cached = index.cached(&path, mtime).map(|entry| &entry.health);
+        let (health, fresh) = check(Path::new(&path), checks, cached)?;
+        project.health = Some(health);
+        if !fresh.is_empty() {
+            computed.push((path, mtime, fresh));
+        }
+    }
+    if !computed.is_empty() {
+        index::update(|index| {
+            for (path, mtime, fresh) in computed {
+                index.entry(path, mtime).health.extend(fresh);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// How many of `projects` fail each check, in check order.
+pub fn missing_counts<'a>(projects: impl IntoIterator<Item = &'a Project>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for result in projects
+        .into_iter()
+        .filter_map(|project| project.health.as_ref())
+        .flat_map(|health| health.results.iter())
+    {
+        let missing = usize::from(!result.passed);
+        match counts.iter_mut().find(|(name, _)| *name == result.name) {
+            Some((_, count)) => *count += missing,
+            None => counts.push((result.name.clone(), missing)),
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use chrono::{Local, NaiveDate};
+
+    fn flags(dir: &TempDir) -> String {
+        check(dir.path(), &default_checks(), None)
+            .unwrap()
+            .0
+            .flags()
+    }
+
+    #[test]
+    fn an_empty_project_fails_every_check() {
+        let dir = TempDir::new();
+        assert_eq!(flags(&dir), "-----");
+    }
+
+    #[test]
+    fn each_check_passes_on_its_own() {
+        for (files, expected) in [
+            (&["README.md"][..], "R----"),
+            (&["readme.md"], "R----"),
+            (&["COPYING"], "-L---"),
+            (&["LICENSE-APACHE"], "-L---"),
+            (&[".git/HEAD"], "--G--"),
+        ] {
+            let dir = TempDir::new();
+            for file in files {
+                dir.write(file, "");
+            }
+            assert_eq!(flags(&dir), expected, "{files:?}");
+        }
+    }
+
+    #[test]
+    fn a_remote_needs_one_in_the_git_config() {
+        let dir = TempDir::new();
+        dir.write(".git/config", "[core]\n\tbare = false\n");
+        assert_eq!(flags(&dir), "--G--");
+        dir.write(
+            ".git/config",
+            "[remote \"origin\"]\n\turl = https://example.com/app.git\n",
+        );
+        assert_eq!(flags(&dir), "--GO-");
+    }
+
+    #[test]
+    fn a_description_or_tags_count_as_described() {
+        for contents in ["description = \"\"\n", "tags = []\n"] {
+            let dir = TempDir::new();
+            dir.write(".vpm/meta.toml", contents);
+            assert_eq!(flags(&dir), "-----", "{contents:?}");
+        }
+        for contents in ["description = \"An app\"\n", "tags = [\"rust\"]\n"] {
+            let dir = TempDir::new();
+            dir.write(".vpm/meta.toml", contents);
+            assert_eq!(flags(&dir), "----D", "{contents:?}");
+        }
+    }
+
+    #[test]
+    fn configured_checks_come_after_the_built_in_ones() {
+        let dir = TempDir::new();
+        dir.write("Cargo.toml", "");
+        let mut checks = default_checks();
+        checks.push(HealthCheck::new("cargo", 'C', &["Cargo.toml"]));
+        checks.push(HealthCheck::new("ci", 'I', &[".github/workflows"]));
+        let (health, _) = check(dir.path(), &checks, None).unwrap();
+        assert_eq!(health.flags(), "-----C-");
+        let names = health.results.iter().map(|result| result.name.as_str());
+        assert!(names.eq([
+            "readme",
+            "license",
+            "git",
+            "remote",
+            "description",
+            "cargo",
+            "ci"
+        ]));
+    }
+
+    #[test]
+    fn cached_file_checks_are_reused() {
+        let dir = TempDir::new();
+        dir.write("LICENSE", "");
+        let cached = BTreeMap::from([("readme".to_string(), true)]);
+        let (health, fresh) = check(dir.path(), &default_checks(), Some(&cached)).unwrap();
+        assert_eq!(health.flags(), "RL---");
+        assert_eq!(
+            fresh,
+            BTreeMap::from([("license".to_string(), true), ("git".to_string(), false)])
+        );
+    }
+
+    #[test]
+    fn missing_counts_follow_the_check_order() {
+        let (readme, empty) = (TempDir::new(), TempDir::new());
+        readme.write("README.md", "");
+        let projects = [readme, empty].map(|dir| {
+            let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+            let mut project = Project::new("/projects", 1, "app", date, Local::now());
+            project.health = Some(check(dir.path(), &default_checks(), None).unwrap().0);
+            project
+        });
+        assert_eq!(
+            missing_counts(&projects),
+            [
+                ("readme".to_string(), 1),
+                ("license".to_string(), 2),
+                ("git".to_string(), 2),
+                ("remote".to_string(), 2),
+                ("description".to_string(), 2),
+            ]
+        );
+    }
+}
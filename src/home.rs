@@ -0,0 +1,243 @@
+use chrono::NaiveDate;
+use directories::BaseDirs;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::Status;
+
+/// How projects are arranged under the project home.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Projects live in `Active/`, `Paused/` and `Archived/` folders.
+    #[default]
+    Nested,
+    /// Projects live directly in the project home and are all active.
+    Flat,
+}
+
+static MODE: OnceLock<LayoutMode> = OnceLock::new();
+static ID_WIDTH: OnceLock<usize> = OnceLock::new();
+
+/// Hex digits of the ids in project folder names when no project needs more, as in `p0A`.
+pub const MIN_ID_WIDTH: usize = 2;
+
+impl LayoutMode {
+    /// Flat when `root` has projects but none of the status folders, nested otherwise.
+    pub fn detect(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        let has_status_dirs = [Status::Active, Status::Paused, Status::Archived]
+            .iter()
+            .any(|status| root.join(status.to_string()).is_dir());
+        let has_projects = root
+            .read_dir()
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .any(|entry| is_project_dir_name(&entry.file_name().to_string_lossy()))
+            })
+            .unwrap_or(false);
+        if !has_status_dirs && has_projects {
+            LayoutMode::Flat
+        } else {
+            LayoutMode::Nested
+        }
+    }
+
+    /// The folder holding the projects with `status`.
+    pub fn status_dir(self, root: impl AsRef<Path>, status: Status) -> PathBuf {
+        match self {
+            LayoutMode::Nested => root.as_ref().join(status.to_string()),
+            LayoutMode::Flat => root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+/// Use `mode` for the rest of the run. Only the first call has an effect.
+pub fn set_mode(mode: LayoutMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn mode() -> LayoutMode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+/// Pad the ids of new project folders to `width` hex digits for the rest of the run. Only the
+/// first call has an effect.
+pub fn set_id_width(width: usize) {
+    let _ = ID_WIDTH.set(width.max(MIN_ID_WIDTH));
+}
+
+/// How many hex digits new project folders pad their id to, the widest id in use.
+pub fn id_width() -> usize {
+    ID_WIDTH.get().copied().unwrap_or(MIN_ID_WIDTH)
+}
+
+/// How many hex digits `id` needs.
+pub fn hex_width(id: usize) -> usize {
+    format!("{id:X}").len()
+}
+
+/// Replace a leading `~` in `path` with the home directory, like a shell would.
+pub fn expand_tilde(path: &str) -> String {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => return path.to_string(),
+    };
+    match BaseDirs::new() {
+        Some(dirs) => format!("{}{rest}", dirs.home_dir().display()),
+        None => path.to_string(),
+    }
+}
+
+/// How project folders must be named, for messages.
+pub const NAMING_SCHEME: &str = "p{HEX}-{name}-{YYYY-MM-DD}";
+
+/// What scanning the status folders found: how many projects were read, which folders were
+/// skipped because their name doesn't follow `NAMING_SCHEME`, and which projects were dropped
+/// because another project already has their id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scan {
+    pub parsed: usize,
+    pub skipped: Vec<PathBuf>,
+    pub duplicates: Vec<(usize, PathBuf)>,
+}
+
+static SCAN: Mutex<Scan> = Mutex::new(Scan {
+    parsed: 0,
+    skipped: Vec::new(),
+    duplicates: Vec::new(),
+});
+
+pub fn record_parsed() {
+    SCAN.lock().unwrap().parsed += 1;
+}
+
+pub fn record_skipped(path: PathBuf) {
+    SCAN.lock().unwrap().skipped.push(path);
+}
+
+pub fn record_duplicate(id: usize, path: PathBuf) {
+    SCAN.lock().unwrap().duplicates.push((id, path));
+}
+
+/// What the scan of this run found so far.
+pub fn scan() -> Scan {
+    SCAN.lock().unwrap().clone()
+}
+
+/// A hint for when more than `max_skipped` of the scanned folders were skipped, which usually
+/// means the folders were named by another version of vpm or by hand.
+pub fn naming_hint(parsed: usize, skipped: usize, max_skipped: f64) -> Option<String> {
+    let total = parsed + skipped;
+    if skipped == 0 || (skipped as f64) <= max_skipped * total as f64 {
+        return None;
+    }
+    let found = if parsed == 0 {
+        format!("None of the {total} folders in the status folders are named like {NAMING_SCHEME}, so no projects are shown.")
+    } else {
+        format!("{skipped} of the {total} folders in the status folders are not named like {NAMING_SCHEME} and are not shown.")
+    };
+    Some(format!(
+        "{found}\nRun `vpm doctor` to list them, or `vpm --flat` if the projects live directly in the project home."
+    ))
+}
+
+/// Whether `name` looks like `p{HEX}-{name}-{YYYY-MM-DD}`.
+pub fn is_project_dir_name(name: &str) -> bool {
+    parse_project_dir_name(name).is_ok()
+}
+
+/// The parts of a folder name following `NAMING_SCHEME`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirName {
+    pub id: usize,
+    /// How many hex digits the id is written with, leading zeros included.
+    pub id_width: usize,
+    pub name: String,
+    pub date: NaiveDate,
+}
+
+/// The parts of a folder named like `p{HEX}-{name}-{YYYY-MM-DD}`, or why the name doesn't follow
+/// that scheme.
+pub fn parse_project_dir_name(name: &str) -> Result<DirName, &'static str> {
+    let rest = name.strip_prefix('p').ok_or("it doesn't start with p")?;
+    let parts = rest.split('-').collect::<Vec<_>>();
+    if parts.len() < 5 {
+        return Err("it has too few dash-separated parts");
+    }
+    let id = parts[0];
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("its id isn't hexadecimal");
+    }
+    let id_width = id.len();
+    let id = usize::from_str_radix(id, 16).map_err(|_| "its id is too large")?;
+    let project_name = parts[1..parts.len() - 3].join("-");
+    if project_name.is_empty() {
+        return Err("its name is empty");
+    }
+    let date = NaiveDate::parse_from_str(&parts[parts.len() - 3..].join("-"), "%Y-%m-%d")
+        .map_err(|_| "its date isn't a valid YYYY-MM-DD")?;
+    Ok(DirName {
+        id,
+        id_width,
+        name: project_name,
+        date,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_hint_up_to_the_threshold() {
+        let cases = [
+            (10, 0, 0.5),
+            (0, 0, 0.5),
+            (5, 5, 0.5),
+            (3, 1, 0.25),
+            (9, 1, 0.1),
+            (0, 4, 1.0),
+            (0, 0, 0.0),
+        ];
+        for (parsed, skipped, max_skipped) in cases {
+            assert_eq!(
+                naming_hint(parsed, skipped, max_skipped),
+                None,
+                "{parsed} parsed, {skipped} skipped, max {max_skipped}"
+            );
+        }
+    }
+
+    #[test]
+    fn hints_past_the_threshold() {
+        let cases = [(4, 6, 0.5), (2, 1, 0.25), (8, 2, 0.1), (100, 1, 0.0)];
+        for (parsed, skipped, max_skipped) in cases {
+            let hint = naming_hint(parsed, skipped, max_skipped)
+                .unwrap_or_else(|| panic!("{parsed} parsed, {skipped} skipped, max {max_skipped}"));
+            assert!(
+                hint.starts_with(&format!(
+                    "{skipped} of the {} folders in the status folders are not named like {NAMING_SCHEME}",
+                    parsed + skipped
+                )),
+                "{hint}"
+            );
+            assert!(
+                hint.ends_with("`vpm --flat` if the projects live directly in the project home.")
+            );
+        }
+    }
+
+    #[test]
+    fn says_when_nothing_is_shown() {
+        let hint = naming_hint(0, 3, 0.5).unwrap();
+        assert!(
+            hint.starts_with(&format!(
+                "None of the 3 folders in the status folders are named like {NAMING_SCHEME}, so no projects are shown.\n"
+            )),
+            "{hint}"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{atomic, paths::Paths, Project};
+
+/// Cache of values that are expensive to compute from a project's contents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Index {
+    #[serde(default)]
+    pub projects: BTreeMap<String, IndexEntry>,
+    /// Number of projects in each status folder as of the last full scan, by project home.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub counts: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+/// Cached values for one project directory, valid while its mtime is unchanged.
+/// Values that haven't been computed yet are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub mtime: u64,
+    /// The inferred kind, empty if no rule matched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Results of the file health checks, by check name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub health: BTreeMap<String, bool>,
+}
+
+pub fn index_path() -> Result<PathBuf> {
+    Ok(Paths::resolve()?.index_file())
+}
+
+/// Load the index, starting over with an empty one if it is missing or unreadable.
+pub fn load() -> Index {
+    index_path().and_then(atomic::read_toml).unwrap_or_default()
+}
+
+/// Apply `modify` to the index under the lock. Everything in the index can be computed again,
+/// so failing to write it only warns.
+pub fn update(modify: impl FnOnce(&mut Index)) {
+    let updated = Paths::resolve()
+        .and_then(|paths| atomic::update(&paths.lock_file(), paths.index_file(), modify));
+    if let Err(err) = updated {
+        eprintln!("Warning: could not update the cache: {err}");
+    }
+}
+
+/// Modification time of `path` in seconds since the epoch.
+pub fn mtime(path: impl AsRef<Path>) -> Option<u64> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+impl Index {
+    /// The entry for `path`, if it was cached for `mtime`.
+    pub fn cached(&self, path: &str, mtime: u64) -> Option<&IndexEntry> {
+        self.projects.get(path).filter(|entry| entry.mtime == mtime)
+    }
+
+    /// The entry for `path`, cleared first if it was cached for a different mtime.
+    pub fn entry(&mut self, path: String, mtime: u64) -> &mut IndexEntry {
+        let entry = self.projects.entry(path).or_default();
+        if entry.mtime != mtime {
+            *entry = IndexEntry {
+                mtime,
+                ..Default::default()
+            };
+        }
+        entry
+    }
+}
+
+/// Remember how many projects each status folder of `root` holds, for `project-count --cached`.
+pub fn record_counts<'a>(root: &str, projects: impl IntoIterator<Item = &'a Project>) {
+    let mut counts = BTreeMap::new();
+    for project in projects {
+        *counts.entry(project.status.to_string()).or_insert(0) += 1;
+    }
+    if load().counts.get(root) != Some(&counts) {
+        update(|index| {
+            index.counts.insert(root.to_string(), counts);
+        });
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{Project, Status};
+
+/// Version of the `--json` output format. Adding fields keeps it, removing, renaming or
+/// retyping a field bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Stamp the top-level object of a `--json` output with the schema version.
+pub fn versioned(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("schema".to_string(), SCHEMA_VERSION.into());
+    }
+    value
+}
+
+/// Print a `--json` output, indented so it reads well without piping it through `jq`.
+pub fn print(value: Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    );
+}
+
+/// The fields every `--json` output describing a project has.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectJson {
+    pub id: usize,
+    pub name: String,
+    pub full_name: String,
+    pub path: String,
+    /// ISO-8601 creation date, e.g. `2024-05-01`.
+    pub date: String,
+    /// RFC-3339 timestamp.
+    pub last_accessed: String,
+    pub status: Status,
+}
+
+/// `project` as a JSON object, to add command specific fields to.
+pub fn project(project: &Project) -> Value {
+    serde_json::to_value(project.to_json()).unwrap_or_default()
+}
+
+/// The `--json` output of commands listing projects.
+pub fn projects<'a>(projects: impl IntoIterator<Item = &'a Project>) -> Value {
+    let projects = projects
+        .into_iter()
+        .map(Project::to_json)
+        .collect::<Vec<_>>();
+    versioned(json!({ "projects": projects }))
+}
@@ -0,0 +1,201 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{index, meta, Project};
+
+/// Maps the presence of a file name or extension in a project's top level to a kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KindRule {
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+    /// Only match when most top-level files have `extension`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub majority: bool,
+}
+
+impl KindRule {
+    fn file(file: &str, kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            file: Some(file.to_string()),
+            extension: None,
+            majority: false,
+        }
+    }
+
+    fn extension(extension: &str, kind: &str, majority: bool) -> Self {
+        Self {
+            kind: kind.to_string(),
+            file: None,
+            extension: Some(extension.to_string()),
+            majority,
+        }
+    }
+}
+
+pub fn default_rules() -> Vec<KindRule> {
+    vec![
+        KindRule::file("Cargo.toml", "rust"),
+        KindRule::file("package.json", "node"),
+        KindRule::file("pyproject.toml", "python"),
+        KindRule::file("requirements.txt", "python"),
+        KindRule::file("go.mod", "go"),
+        KindRule::file("CMakeLists.txt", "c"),
+        KindRule::file("platformio.ini", "hardware"),
+        KindRule::extension("kicad_pro", "hardware", false),
+        KindRule::extension("als", "music", false),
+        KindRule::extension("flp", "music", false),
+        KindRule::extension("md", "writing", true),
+        KindRule::extension("tex", "writing", true),
+    ]
+}
+
+/// Infer a kind from the names of a project's top-level files, trying `rules` in order.
+pub fn infer_kind(file_names: &[String], rules: &[KindRule]) -> Option<String> {
+    let mut extensions = BTreeMap::new();
+    for name in file_names {
+        if let Some(extension) = Path::new(name).extension() {
+            *extensions
+                .entry(extension.to_string_lossy().to_lowercase())
+                .or_insert(0) += 1;
+        }
+    }
+    rules
+        .iter()
+        .find(|rule| {
+            let file_matches = rule
+                .file
+                .as_ref()
+                .is_some_and(|file| file_names.contains(file));
+            let extension_matches = rule.extension.as_ref().is_some_and(|extension| {
+                let count = extensions
+                    .get(&extension.to_lowercase())
+                    .copied()
+                    .unwrap_or(0);
+                if rule.majority {
+                    count * 2 > file_names.len()
+                } else {
+                    count > 0
+                }
+            });
+            file_matches || extension_matches
+        })
+        .map(|rule| rule.kind.clone())
+}
+
+fn top_level_files(path: &str) -> Vec<String> {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Set the kind of every project: the metadata value wins, then the cached or inferred one.
+pub fn resolve_kinds(projects: &mut BTreeMap<usize, Project>, rules: &[KindRule]) -> Result<()> {
+    let index = index::load();
+    let mut inferred = Vec::new();
+    for project in projects.values_mut() {
+        let path = project.get_path();
+        if let Some(kind) = meta::load(&path)?.and_then(|meta| meta.kind) {
+            project.kind = Some(kind);
+            continue;
+        }
+        let Some(mtime) = index::mtime(&path) else {
+            continue;
+        };
+        let cached = index
+            .cached(&path, mtime)
+            .and_then(|entry| entry.kind.clone());
+        let kind = cached.unwrap_or_else(|| {
+            let kind = infer_kind(&top_level_files(&path), rules).unwrap_or_default();
+            inferred.push((path.clone(), mtime, kind.clone()));
+            kind
+        });
+        project.kind = Some(kind).filter(|kind| !kind.is_empty());
+    }
+    if !inferred.is_empty() {
+        index::update(|index| {
+            for (path, mtime, kind) in inferred {
+                index.entry(path, mtime).kind = Some(kind);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn infers_kinds_with_the_default_rules() {
+        let cases: &[(&[&str], Option<&str>)] = &[
+            (&["Cargo.toml", "README.md"], Some("rust")),
+            (&["package.json"], Some("node")),
+            (&["pyproject.toml"], Some("python")),
+            (&["requirements.txt", "main.py"], Some("python")),
+            (&["go.mod"], Some("go")),
+            (&["CMakeLists.txt"], Some("c")),
+            (&["platformio.ini"], Some("hardware")),
+            (&["board.KICAD_PRO"], Some("hardware")),
+            (&["song.als"], Some("music")),
+            (&["beat.flp", "notes.txt"], Some("music")),
+            (&["a.md", "b.md", "c.txt"], Some("writing")),
+            (&["thesis.tex"], Some("writing")),
+            (&["cargo.toml"], None),
+            (&["a.md", "b.txt"], None),
+            (&[], None),
+        ];
+        for (names, expected) in cases {
+            assert_eq!(
+                infer_kind(&files(names), &default_rules()).as_deref(),
+                *expected,
+                "{names:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn earlier_rules_win() {
+        // A Rust project with mostly markdown docs is still Rust.
+        let names = files(&["Cargo.toml", "a.md", "b.md", "c.md"]);
+        assert_eq!(
+            infer_kind(&names, &default_rules()).as_deref(),
+            Some("rust")
+        );
+        // Files beat extensions only by coming first.
+        let rules = [
+            KindRule::extension("md", "writing", false),
+            KindRule::file("Cargo.toml", "rust"),
+        ];
+        assert_eq!(infer_kind(&names, &rules).as_deref(), Some("writing"));
+        // Two project files: the first rule decides, not the first file.
+        let names = files(&["package.json", "Cargo.toml"]);
+        assert_eq!(
+            infer_kind(&names, &default_rules()).as_deref(),
+            Some("rust")
+        );
+    }
+
+    #[test]
+    fn majority_needs_more_than_half() {
+        let rules = [KindRule::extension("md", "writing", true)];
+        let kind = |names: &[&str]| infer_kind(&files(names), &rules);
+        assert_eq!(kind(&["a.md", "b.md", "c.txt"]).as_deref(), Some("writing"));
+        assert_eq!(kind(&["a.md", "b.txt"]), None);
+        assert_eq!(kind(&["a.MD"]).as_deref(), Some("writing"));
+    }
+}
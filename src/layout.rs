@@ -0,0 +1,229 @@
+use std::borrow::Cow;
+
+const ELLIPSIS: char = '…';
+const TAB_WIDTH: usize = 8;
+/// The name column never shrinks below this many characters, even if the row overflows.
+pub const MIN_NAME_WIDTH: usize = 8;
+
+/// Width left for the name column once the fixed columns take `fixed_width` of `total_width`.
+pub fn name_width(fixed_width: usize, total_width: usize) -> usize {
+    total_width.saturating_sub(fixed_width).max(MIN_NAME_WIDTH)
+}
+
+/// Shorten `text` to at most `width` characters, marking the cut with an ellipsis.
+pub fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text.chars().count() <= width {
+        return Cow::Borrowed(text);
+    }
+    let mut truncated = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    truncated.push(ELLIPSIS);
+    Cow::Owned(truncated)
+}
+
+/// Replace tabs with spaces up to the next tab stop, as a terminal would display them.
+pub fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::new();
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = TAB_WIDTH - display_width(&expanded) % TAB_WIDTH;
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+        } else {
+            expanded.push(c);
+        }
+    }
+    expanded
+}
+
+/// Number of characters `text` takes up on screen, not counting ANSI colour codes.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip up to and including the final letter of the escape sequence.
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Fit a tab-separated row into `total_width` by shrinking only its `shrink` column, the last
+/// one when `None`. The column doesn't shrink below `MIN_NAME_WIDTH`, so the row can still
+/// overflow.
+pub fn fit_row(row: &str, shrink: Option<usize>, total_width: usize) -> Cow<'_, str> {
+    let trimmed = row.trim_end_matches('\t');
+    let fits = |row: &str| display_width(&expand_tabs(row)) <= total_width;
+    if fits(trimmed) {
+        return Cow::Borrowed(row);
+    }
+    let cells = trimmed.split('\t').collect::<Vec<_>>();
+    let shrink = shrink.unwrap_or(cells.len() - 1).min(cells.len() - 1);
+    let with_cell = |cell: &str| {
+        let mut cells = cells.clone();
+        cells[shrink] = cell;
+        cells.join("\t")
+    };
+    let mut fitted = trimmed.to_string();
+    // Tab stops after the column move as it shrinks, so try each width rather than subtracting.
+    for width in (MIN_NAME_WIDTH..display_width(cells[shrink])).rev() {
+        fitted = with_cell(&truncate(cells[shrink], width));
+        if fits(&fitted) {
+            break;
+        }
+    }
+    Cow::Owned(format!("{fitted}{}", &row[trimmed.len()..]))
+}
+
+/// Lay out `rows` under `headers` in columns two spaces apart, sized to their widest cell. When
+/// the table is wider than `total_width`, the `shrink` column is truncated to make it fit.
+pub fn table(
+    headers: &[&str],
+    rows: &[Vec<String>],
+    shrink: usize,
+    total_width: Option<usize>,
+) -> Vec<String> {
+    const GAP: &str = "  ";
+    let mut widths = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            rows.iter()
+                .map(|row| display_width(&row[column]))
+                .chain([header.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    if let Some(total_width) = total_width {
+        let fixed = widths.iter().sum::<usize>() - widths[shrink] + GAP.len() * (widths.len() - 1);
+        widths[shrink] = widths[shrink].min(name_width(fixed, total_width));
+    }
+    let line = |cells: Vec<Cow<'_, str>>| {
+        let last = cells.len() - 1;
+        cells
+            .into_iter()
+            .enumerate()
+            .map(|(column, cell)| {
+                let cell = if column == shrink {
+                    Cow::Owned(truncate(&cell, widths[column]).into_owned())
+                } else {
+                    cell
+                };
+                let padding = widths[column].saturating_sub(display_width(&cell));
+                if column == last {
+                    cell.into_owned()
+                } else {
+                    format!("{cell}{}", " ".repeat(padding))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(GAP)
+    };
+    std::iter::once(line(
+        headers
+            .iter()
+            .map(|header| Cow::Borrowed(*header))
+            .collect(),
+    ))
+    .chain(rows.iter().map(|row| {
+        line(
+            row.iter()
+                .map(|cell| Cow::Borrowed(cell.as_str()))
+                .collect(),
+        )
+    }))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_width_never_drops_below_the_minimum() {
+        for (fixed, total, expected) in [(10, 80, 70), (70, 80, 10), (75, 80, 8), (90, 80, 8)] {
+            assert_eq!(name_width(fixed, total), expected, "{fixed} of {total}");
+        }
+    }
+
+    #[test]
+    fn truncate_marks_the_cut() {
+        let cases = [
+            ("project", 10, "project"),
+            ("project", 7, "project"),
+            ("project", 6, "proje…"),
+            ("project", 1, "…"),
+            ("日本語のプロジェクト", 4, "日本語…"),
+        ];
+        for (text, width, expected) in cases {
+            assert_eq!(truncate(text, width), expected, "{text} at {width}");
+        }
+    }
+
+    #[test]
+    fn fit_row_shrinks_the_last_column_by_default() {
+        let row = "  1\tsome-long-project-name\t";
+        let cases = [
+            (80, "  1\tsome-long-project-name\t"),
+            (20, "  1\tsome-long-p…\t"),
+            (16, "  1\tsome-lo…\t"),
+            // The column keeps `MIN_NAME_WIDTH` characters even if the row overflows.
+            (4, "  1\tsome-lo…\t"),
+        ];
+        for (width, expected) in cases {
+            assert_eq!(fit_row(row, None, width), expected, "at {width}");
+        }
+    }
+
+    #[test]
+    fn fit_row_shrinks_the_name_before_a_description() {
+        let row = "  1\tsome-long-project-name\tshort\t";
+        let cases = [
+            (80, "  1\tsome-long-project-name\tshort\t"),
+            // The description starts on the next tab stop after the name.
+            (37, "  1\tsome-long-project-name\tshort\t"),
+            (36, "  1\tsome-long-proj…\tshort\t"),
+            (29, "  1\tsome-long-proj…\tshort\t"),
+            (28, "  1\tsome-lo…\tshort\t"),
+        ];
+        for (width, expected) in cases {
+            assert_eq!(fit_row(row, Some(1), width), expected, "at {width}");
+        }
+    }
+
+    #[test]
+    fn table_shrinks_its_column_and_keeps_padded_cells() {
+        let rows = vec![
+            vec![
+                "1".to_string(),
+                "a-long-project".to_string(),
+                format!("{:>5}", "3d"),
+            ],
+            vec![
+                "10".to_string(),
+                "short".to_string(),
+                format!("{:>5}", "120d"),
+            ],
+        ];
+        let headers = ["ID", "NAME", &format!("{:>5}", "IDLE")];
+        assert_eq!(
+            table(&headers, &rows, 1, Some(24)),
+            [
+                "ID  NAME            IDLE",
+                "1   a-long-proje…     3d",
+                "10  short           120d",
+            ]
+        );
+    }
+
+    #[test]
+    fn fit_row_ignores_colour_codes() {
+        let row = "\x1b[32m(active)\x1b[0m\tname";
+        assert_eq!(fit_row(row, Some(1), 12), row);
+    }
+}
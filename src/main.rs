@@ -1,22 +1,85 @@
-use chrono::{DateTime, Local, NaiveDate, NaiveTime};
-use clap::{Parser, Subcommand, ValueEnum};
+use accessed::AccessedSource;
+use chrono::{DateTime, Local, NaiveDate};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use color_eyre::eyre::{anyhow, Result};
+use config::{Config, Setting, Source};
+use doctor::{DateFix, FixAction};
+use error::VpmError;
+use export::LocalAction;
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use health::Health;
+use home::LayoutMode;
 use itertools::Itertools;
+use meta::ProjectMeta;
+use paths::Paths;
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use sort::Sort;
+use stats::{Grouping, Sample};
 use std::{
-    cmp::Ordering,
-    collections::BTreeMap,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashSet},
     env,
     fmt::Display,
-    fs, io,
-    path::Path,
-    process::{Command, Stdio},
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
+    process::{self, Command},
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
+use template::VariableUse;
+use tombstone::Tombstone;
+use when::When;
+mod accessed;
+mod atomic;
+mod clipboard;
+mod config;
+mod create;
+mod display;
+mod doctor;
+mod editor;
+mod error;
+mod exec;
+mod export;
+mod fsops;
+mod git;
+mod github;
+mod health;
+mod home;
+mod index;
+mod json;
+mod kind;
+mod layout;
+mod meta;
+mod opener;
+mod paths;
+mod peek;
+mod pick;
+mod rename;
+mod scheduler;
 mod shells;
+mod size;
+mod sort;
+mod stats;
+mod template;
+mod terminal;
+#[cfg(test)]
+mod test_util;
+mod tombstone;
+mod trash;
 mod tui;
+mod when;
+mod wizard;
 
-#[derive(Default, Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Default, Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub enum Status {
     Archived,
     Paused,
@@ -64,11 +127,22 @@ pub struct Project {
     pub date: NaiveDate,
     pub last_accessed: DateTime<Local>,
     pub status: Status,
+    /// The project home the project's folder is in.
+    pub root: String,
+    /// Hex digits of the id in the folder name, see `home::id_width`.
+    pub id_width: usize,
+    pub kind: Option<String>,
+    pub health: Option<Health>,
+    pub description: Option<String>,
+    pub accessed_source: AccessedSource,
+    /// The project's `.vpm/meta.toml`, `None` for folders without one.
+    pub meta: Option<ProjectMeta>,
     args: Option<Args>,
 }
 
 impl Project {
     pub fn new(
+        root: impl Into<String>,
         id: usize,
         name: impl Into<String>,
         date: NaiveDate,
@@ -80,16 +154,38 @@ impl Project {
             date,
             last_accessed,
             status: Status::default(),
+            root: root.into(),
+            id_width: home::id_width(),
+            kind: None,
+            health: None,
+            description: None,
+            accessed_source: AccessedSource::default(),
+            meta: None,
             args: None,
         }
     }
+    /// The project as `--json` outputs it. Unlike `Display`, it doesn't depend on `args`.
+    pub fn to_json(&self) -> json::ProjectJson {
+        json::ProjectJson {
+            id: self.id,
+            name: self.name.clone(),
+            full_name: self.full_name(),
+            path: self.get_path(),
+            date: self.date.to_string(),
+            last_accessed: self.last_accessed.to_rfc3339(),
+            status: self.status,
+        }
+    }
     pub fn get_path(&self) -> String {
-        format!(
-            "{}/{}/{}",
-            env::var("PROJECT_HOME").unwrap(),
-            self.status,
-            self.full_name()
-        )
+        self.path_in(&self.root)
+    }
+    /// The project's folder under the project home `root`.
+    pub fn path_in(&self, root: impl AsRef<Path>) -> String {
+        home::mode()
+            .status_dir(root, self.status)
+            .join(self.full_name())
+            .to_string_lossy()
+            .into_owned()
     }
     pub fn with_args(mut self, args: &Args) -> Self {
         self.args = Some(args.to_owned());
@@ -99,19 +195,71 @@ impl Project {
         self.status = status;
         self
     }
+    pub fn with_id_width(mut self, id_width: usize) -> Self {
+        self.id_width = id_width;
+        self
+    }
     pub fn full_name(&self) -> String {
         format!(
-            "p{:02X}-{}-{}",
-            self.id,
+            "p{}-{}-{}",
+            self.hex_id(),
             self.name,
             self.date.format("%Y-%m-%d")
         )
     }
-    pub fn set_status(&mut self, status: Status) -> io::Result<()> {
+    /// The id as written in the folder name, zero-padded to `id_width` hex digits.
+    pub fn hex_id(&self) -> String {
+        format!("{:0width$X}", self.id, width = self.id_width)
+    }
+    pub fn set_status(&mut self, status: Status) -> Result<()> {
+        if status == self.status {
+            return Ok(());
+        }
+        if home::mode() == LayoutMode::Flat {
+            return Err(anyhow!(
+                "Status changes need Active/, Paused/ and Archived/ folders in the project home"
+            ));
+        }
         let old_path = self.get_path();
+        let new_path = self.clone().with_status(status).get_path();
+        if let Some(status_dir) = Path::new(&new_path).parent() {
+            fs::create_dir_all(status_dir)
+                .map_err(|err| anyhow!("Failed to create {}: {err}", status_dir.display()))?;
+        }
+        if Path::new(&new_path).exists() {
+            return Err(anyhow!("{new_path} already exists!"));
+        }
+        fsops::move_dir(Path::new(&old_path), Path::new(&new_path))?;
         self.status = status;
-        let new_path = self.get_path();
-        fs::rename(old_path, new_path)
+        Ok(())
+    }
+    /// Move the project to `status` and record the change in its history.
+    pub fn change_status(&mut self, status: Status, message: Option<String>) -> Result<()> {
+        self.set_status(status)?;
+        meta::record_status(self.get_path(), status, message)
+    }
+    /// Days since the project was created.
+    pub fn age_days(&self) -> i64 {
+        (Local::now().date_naive() - self.date).num_days()
+    }
+    /// Whole days since the project was last accessed.
+    pub fn days_since_access(&self) -> i64 {
+        (Local::now() - self.last_accessed).num_days()
+    }
+}
+
+/// Describe an age in days the way a person would, e.g. `3 weeks` or `2 years`.
+pub fn human_age(days: i64) -> String {
+    let plural = |n: i64, unit: &str| match n {
+        1 => format!("1 {unit}"),
+        n => format!("{n} {unit}s"),
+    };
+    match days {
+        ..=0 => "today".to_string(),
+        1..=13 => plural(days, "day"),
+        14..=59 => plural(days / 7, "week"),
+        60..=364 => plural(days / 30, "month"),
+        _ => plural(days / 365, "year"),
     }
 }
 
@@ -132,13 +280,36 @@ impl Display for Project {
                     write!(f, "({})\t", self.last_accessed)?;
                 }
                 if args.status {
-                    write!(f, "({:^8})\t", self.status)?;
+                    let status = format!("({:^8})", self.status);
+                    if args.color {
+                        write!(f, "{}\t", display::status_color(&status, self.status))?;
+                    } else {
+                        write!(f, "{status}\t")?;
+                    }
+                }
+                if args.show_kind {
+                    write!(f, "{:8}\t", self.kind.as_deref().unwrap_or("-"))?;
+                }
+                if args.age_column {
+                    write!(f, "{:>10}\t", human_age(self.age_days()))?;
+                }
+                if args.days_since_access_column {
+                    write!(f, "{:>5}\t", format!("{}d", self.days_since_access()))?;
+                }
+                if args.health {
+                    let flags = self.health.as_ref().map(Health::flags);
+                    write!(f, "{}\t", flags.as_deref().unwrap_or("-"))?;
                 }
                 if args.full_name {
                     write!(f, "{}\t", self.full_name())?;
                 } else if !args.no_name {
                     write!(f, "{}\t", self.name)?;
                 }
+                if args.show_description {
+                    let description = self.description.as_deref().unwrap_or_default();
+                    let first_line = description.lines().next().unwrap_or("-");
+                    write!(f, "{first_line}\t")?;
+                }
 
                 Ok(())
             }
@@ -165,30 +336,167 @@ pub struct Args {
     accessed: bool,
     #[arg(short, long, help = "Print the status of the projects")]
     status: bool,
+    #[arg(short = 'k', long, help = "Print the kind of the projects")]
+    show_kind: bool,
+    #[arg(
+        short = 'D',
+        long = "description",
+        help = "Print the description of the projects"
+    )]
+    show_description: bool,
+    #[arg(
+        long = "json",
+        global = true,
+        help = "Print the output as JSON: list, search, path, status, peek, stats, info and tag stats"
+    )]
+    json_output: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Print how long ago the projects were created"
+    )]
+    age_column: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Print the number of days since the projects were last accessed, e.g. `42d`"
+    )]
+    days_since_access_column: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Print health flags: README, License, Git, Origin remote, Description"
+    )]
+    health: bool,
+    #[arg(
+        long,
+        global = true,
+        visible_alias = "no-status-dirs",
+        help = "Read projects directly from the project home instead of its status folders"
+    )]
+    flat: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Root of the projects folder, overrides $PROJECT_HOME and `project_home`"
+    )]
+    project_home: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PROGRAM",
+        help = "Editor command for `vpm code`, instead of `editor.command`, \
+                $VPM_EDITOR, $VISUAL or $EDITOR"
+    )]
+    editor: Option<String>,
+    #[arg(long, global = true, help = "Never print colours, even on a terminal")]
+    no_color: bool,
+    #[arg(
+        short,
+        long,
+        global = true,
+        help = "Don't print hints, like the one about unexpected folder names"
+    )]
+    quiet: bool,
+    /// Resolved from `--no-color`, the environment and the terminal by `display::should_use_color`.
+    #[arg(skip)]
+    color: bool,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+impl Args {
+    /// Index of the name column in a project row, or `None` when the row shows no name.
+    fn name_column(&self) -> Option<usize> {
+        let before = [
+            self.id,
+            self.date,
+            self.accessed,
+            self.status,
+            self.show_kind,
+            self.age_column,
+            self.days_since_access_column,
+            self.health,
+        ];
+        (self.full_name || !self.no_name).then(|| before.iter().filter(|&&shown| shown).count())
+    }
+}
+
 #[derive(Subcommand, Debug, Clone)]
 enum Commands {
-    #[command(about = "Get the status of a project")]
+    #[command(about = "Get the status of a project, or move it to another status")]
     Status {
-        #[clap(help = "Decimal ID of the project")]
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
         id: usize,
+        #[clap(help = "New status of the project: `active`, `paused` or `archived`")]
+        status: Option<Status>,
     },
-    #[command(about = "Pause a project")]
+    #[command(about = "Pause projects")]
     Pause {
-        #[clap(help = "Decimal ID of the project")]
-        id: usize,
+        #[clap(
+            value_parser = parse_id,
+            help = "IDs of the projects, in decimal or in hex like 0x1A or p1A",
+            required_unless_present = "pick"
+        )]
+        ids: Vec<usize>,
+        #[arg(
+            long,
+            conflicts_with = "ids",
+            help = "Pick the projects from a numbered list"
+        )]
+        pick: bool,
     },
-    #[command(about = "Archive a project")]
+    #[command(about = "Archive projects")]
     Archive {
-        #[clap(help = "Decimal ID of the project")]
-        id: usize,
+        #[clap(
+            value_parser = parse_id,
+            help = "IDs of the projects, in decimal or in hex like 0x1A or p1A",
+            required_unless_present = "pick"
+        )]
+        ids: Vec<usize>,
+        #[arg(
+            long,
+            conflicts_with = "ids",
+            help = "Pick the projects from a numbered list"
+        )]
+        pick: bool,
+        #[arg(short, long, help = "Why the project is being archived")]
+        message: Option<String>,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Export the project as a verified tarball into DIR and move the local copy to the trash"
+        )]
+        to: Option<PathBuf>,
+        #[arg(long, requires = "to", help = "Keep the local copy after exporting it")]
+        keep_local: bool,
+        #[arg(
+            long,
+            requires = "to",
+            conflicts_with = "keep_local",
+            help = "Delete the local copy after exporting it instead of trashing it"
+        )]
+        delete_local: bool,
     },
-    #[command(about = "Resume a project. Set status to active")]
+    #[command(about = "Resume projects. Set status to active", alias = "activate")]
     Resume {
-        #[clap(help = "Decimal ID of the project")]
+        #[clap(
+            value_parser = parse_id,
+            help = "IDs of the projects, in decimal or in hex like 0x1A or p1A",
+            required_unless_present = "pick"
+        )]
+        ids: Vec<usize>,
+        #[arg(
+            long,
+            conflicts_with = "ids",
+            help = "Pick the projects from a numbered list"
+        )]
+        pick: bool,
+    },
+    #[command(about = "Show the status changes of a project", alias = "log")]
+    StatusHistory {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
         id: usize,
     },
     #[command(about = "List all projects")]
@@ -196,8 +504,8 @@ enum Commands {
         #[arg(
             short,
             long,
-            help = "What to sort by, can be multiple columns in order.",
-            default_value = "id"
+            help = "What to sort by, can be multiple columns in order. \
+                    Defaults to `list.default_sort`, which is id unless configured"
         )]
         sort: Vec<Sort>,
         #[arg(short, long, help = "Reverse the sort")]
@@ -211,43 +519,336 @@ enum Commands {
         limit: usize,
         #[arg(
             long = "st",
-            help = "Filter by status. Can be `active`, `paused`, or `archived`"
+            visible_alias = "status",
+            value_delimiter = ',',
+            help = "Filter by status. Can be `active`, `paused`, or `archived`, \
+                    repeated or comma-separated"
         )]
         status: Vec<Status>,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Only show the N most recently accessed projects of each status"
+        )]
+        only_recent_n: Option<usize>,
+        #[arg(long, help = "Don't truncate long names to fit the terminal width")]
+        no_truncate: bool,
+        #[arg(long, help = "Filter by kind, e.g. `rust` or `writing`")]
+        kind: Vec<String>,
+        #[arg(
+            long,
+            value_name = "ID",
+            help = "Only show projects with an id greater than ID"
+        )]
+        since_id: Option<usize>,
+        #[arg(
+            long,
+            value_name = "ID",
+            help = "Only show projects with an id less than ID"
+        )]
+        before_id: Option<usize>,
+        #[arg(
+            long,
+            value_name = "TEXT",
+            help = "Only show projects whose name contains TEXT"
+        )]
+        name_contains: Option<String>,
+        #[arg(
+            long,
+            value_name = "REGEX",
+            conflicts_with = "name_contains",
+            help = "Only show projects whose name matches REGEX"
+        )]
+        name_regex: Option<String>,
+        #[arg(long, help = "Match --name-contains and --name-regex case-sensitively")]
+        case_sensitive: bool,
+        #[arg(
+            long,
+            value_name = "WHEN",
+            help = "Only show projects created on or after WHEN, e.g. 2024-05, 90d or yesterday"
+        )]
+        since: Option<When>,
+        #[arg(
+            long,
+            value_name = "WHEN",
+            help = "Only show projects created on or before WHEN, e.g. 2024-05-01, 1y or today"
+        )]
+        until: Option<When>,
+        #[arg(
+            long = "accessed-column",
+            help = "Print the time the projects were last accessed, like the global --accessed"
+        )]
+        accessed_col: bool,
+        #[arg(long, help = "Only show projects with a non-empty .vpm/notes.md")]
+        with_notes: bool,
+        #[arg(
+            long,
+            conflicts_with = "with_notes",
+            help = "Only show projects without notes"
+        )]
+        without_notes: bool,
+        #[arg(
+            long,
+            help = "Print an `export VPM_PROJECT_<NAME>=<path>` line for each project instead"
+        )]
+        format_shell_all: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["format_shell_all", "json_output"],
+            help = "Print an aligned table with headers on a terminal, plain rows otherwise"
+        )]
+        table: bool,
+    },
+    #[command(about = "List the most recently accessed projects")]
+    Recent {
+        #[clap(default_value = "5", help = "How many projects to list")]
+        n: usize,
     },
     #[command(about = "Create a new project")]
     New {
-        #[clap(help = "Name of the project")]
-        name: String,
-        #[arg(short, long, help = "Template to use")]
+        #[clap(
+            help = "Name of the project",
+            required_unless_present = "from_clipboard"
+        )]
+        name: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "name",
+            help = "Use the text on the clipboard as the name of the project"
+        )]
+        from_clipboard: bool,
+        #[arg(
+            short,
+            long,
+            help = "Template to use, `?` to pick one from a list, defaults to $VPM_DEFAULT_TEMPLATE"
+        )]
         template: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "template",
+            help = "Don't use $VPM_DEFAULT_TEMPLATE"
+        )]
+        no_template: bool,
+        #[arg(
+            long,
+            help = "Open the project and its notes side by side using `editor.split_command`"
+        )]
+        open_split: bool,
+        #[arg(long, help = "Open the project in a new tmux window")]
+        open_tmux: bool,
+        #[arg(
+            long,
+            value_parser = clap::value_parser!(u8).range(1..=5),
+            help = "Priority of the project, from 1 (highest) to 5 (lowest)"
+        )]
+        priority: Option<u8>,
+        #[arg(
+            long,
+            group = "echo",
+            conflicts_with = "json_output",
+            help = "Only print the decimal ID of the new project"
+        )]
+        echo_id: bool,
+        #[arg(
+            long,
+            group = "echo",
+            conflicts_with = "json_output",
+            help = "Only print the path of the new project"
+        )]
+        echo_path: bool,
+        #[arg(
+            long,
+            group = "echo",
+            conflicts_with = "json_output",
+            help = "Only print the full name of the new project"
+        )]
+        echo_full_name: bool,
+        #[arg(
+            long,
+            requires = "open_tmux",
+            help = "Split the current tmux pane instead of opening a new window"
+        )]
+        tmux_split: bool,
     },
-    #[command(about = "Open a project in VSCode")]
+    #[command(about = "Open a project in an editor, VSCode unless configured otherwise")]
     Code {
-        #[clap(help = "Decimal ID of the project")]
+        #[clap(help = "ID of the project, in decimal or in hex like 0x1A or p1A, \
+                    or a name that fuzzy matches a single project best")]
+        id: String,
+        #[arg(long, conflicts_with = "editor", help = "Open the project in Zed")]
+        zed: bool,
+        #[arg(
+            long,
+            visible_alias = "wait",
+            help = "Run the editor in this terminal and wait for it, \
+                    which known terminal editors like vim always do"
+        )]
+        terminal: bool,
+        #[arg(
+            long,
+            conflicts_with = "zed",
+            help = "VSCode profile to open the project with, needs VSCode 1.75 or newer"
+        )]
+        profile: Option<String>,
+    },
+    #[command(about = "Open a project folder in the default file manager")]
+    Open {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
         id: usize,
     },
+    #[command(about = "Show the details of a project")]
+    Info {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
+        id: usize,
+        #[arg(
+            long,
+            help = "Don't compute the size on disk, which walks the whole project"
+        )]
+        no_size: bool,
+    },
+    #[command(about = "Show the top-level contents of a project")]
+    Peek {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
+        id: usize,
+        #[arg(short, long, default_value = "1", help = "How many levels to show")]
+        depth: usize,
+    },
+    #[command(about = "Print the number of projects, fast enough for a shell prompt")]
+    ProjectCount {
+        #[arg(long, help = "Only count projects with this status")]
+        status: Option<Status>,
+        #[arg(
+            long,
+            help = "Format of the output, `{count}` is replaced by the count, e.g. \"({count} projects)\""
+        )]
+        format: Option<String>,
+        #[arg(
+            long,
+            help = "Use the counts from the last scan instead of reading the folders"
+        )]
+        cached: bool,
+    },
     #[command(about = "Get the path of a project")]
     Path {
-        #[clap(help = "Decimal ID of the project")]
+        #[clap(help = "ID of the project, in decimal or in hex like 0x1A or p1A, \
+                    or a name that fuzzy matches a single project best")]
+        id: String,
+        #[arg(
+            long,
+            conflicts_with = "json_output",
+            help = "Print an `export VPM_PROJECT_<NAME>=<path>` line to source from a shell"
+        )]
+        format_shell: bool,
+    },
+    #[command(about = "Move a project to another status, like `vpm status <id> <status>`")]
+    Move {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
         id: usize,
+        #[arg(
+            long,
+            help = "New status of the project: `active`, `paused` or `archived`"
+        )]
+        status: Status,
     },
-    #[command(about = "Rename a project")]
-    Rename {
-        #[clap(help = "Decimal ID of the project")]
+    #[command(about = "Copy a project to a new project with the next ID")]
+    Duplicate {
+        #[clap(value_parser = parse_id, help = "ID of the project to copy, in decimal or in hex like 0x1A or p1A")]
         id: usize,
-        #[clap(help = "New name of the project")]
+        #[clap(help = "Name of the new project")]
         name: String,
     },
+    #[command(about = "Show or change the description and tags of a project")]
+    Meta {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
+        id: usize,
+        #[arg(long, help = "Replace the description")]
+        description: Option<String>,
+        #[arg(long, help = "Add a tag, can be repeated")]
+        tag: Vec<String>,
+    },
+    #[command(about = "Set the description of a project")]
+    Describe {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
+        id: usize,
+        #[clap(help = "New description, or `-` to read it from stdin")]
+        description: String,
+    },
+    #[command(about = "Create a git repository in a project")]
+    GitInit {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
+        id: usize,
+        #[arg(long, help = "Git template directory, passed to `git init --template`")]
+        template: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "File to copy to .gitignore, instead of `git.gitignore`"
+        )]
+        gitignore: Option<String>,
+        #[arg(long, help = "Stage everything and commit it as `Initial commit`")]
+        initial_commit: bool,
+    },
+    #[command(
+        about = "Pad the ids of all project folders to the same number of hex digits, \
+                 so that they sort by id"
+    )]
+    Migrate {
+        #[arg(short, long, help = "Rename without asking for confirmation")]
+        force: bool,
+    },
+    #[command(about = "Delete projects from disk for good")]
+    Delete {
+        #[clap(
+            value_parser = parse_id,
+            help = "IDs of the projects, in decimal or in hex like 0x1A or p1A",
+            required_unless_present = "pick"
+        )]
+        ids: Vec<usize>,
+        #[arg(
+            long,
+            conflicts_with = "ids",
+            help = "Pick the projects from a numbered list"
+        )]
+        pick: bool,
+        #[arg(short, long, help = "Delete without asking for confirmation")]
+        force: bool,
+    },
+    #[command(about = "Rename a project")]
+    Rename {
+        #[clap(
+            help = "ID of the project, in decimal or in hex like 0x1A or p1A, or its current name",
+            required_unless_present = "edit"
+        )]
+        id: Option<String>,
+        #[clap(help = "New name of the project", required_unless_present = "edit")]
+        name: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "edit",
+            help = "Fall back to a fuzzy match when no project has exactly that name"
+        )]
+        fuzzy: bool,
+        #[arg(
+            short,
+            long,
+            conflicts_with_all = ["id", "name"],
+            help = "Rename projects in bulk by editing a list in $EDITOR"
+        )]
+        edit: bool,
+    },
     #[command(about = "Search for a project")]
     Search {
-        #[clap(help = "Pattern to search for")]
-        pattern: String,
+        #[clap(help = "Pattern to search for, read from stdin when omitted, \
+                    or matching everything with --tag")]
+        pattern: Option<String>,
         #[arg(
             long = "st",
+            visible_alias = "status",
             help = "Filter by status. Can be `active`, `paused`, or `archived`"
         )]
         status: Vec<Status>,
+        #[arg(long, help = "Only search projects with this tag, ignoring case")]
+        tag: Option<String>,
         #[arg(
             short,
             long,
@@ -255,30 +856,191 @@ enum Commands {
             help = "Limit the number of results, 0 for no limit"
         )]
         limit: usize,
+        #[arg(long, help = "List recently accessed projects when nothing matches")]
+        fallback_list: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["search_full_name", "in_description"],
+            help = "Match against the name only, which is the default unless \
+                    --search-full-name or --in-description is given"
+        )]
+        name_only: bool,
+        #[arg(
+            long,
+            help = "Match against the full name, including the hex id and date, e.g. `2023-06`"
+        )]
+        search_full_name: bool,
+        #[arg(
+            long,
+            default_value = "0",
+            help = "Drop matches scoring below this, so a stray letter in common isn't a match"
+        )]
+        min_score: i64,
+        #[arg(
+            long,
+            help = "Also match against the project descriptions, weighted below the names"
+        )]
+        in_description: bool,
     },
-    #[command(about = "Init shell bindings. This will create two functions: j and pj.")]
+    #[command(
+        about = "Init shell bindings. This will create two functions: j and pj.",
+        long_about = "Init shell bindings. This will create two functions: j and pj. \
+            Without a shell, it is detected from $SHELL or the parent process."
+    )]
     Init {
         #[command(subcommand)]
-        shell: InitShells,
+        shell: Option<InitShells>,
     },
-    #[command(about = "Create a new template from a project")]
-    Template {
-        #[clap(help = "ID of the project")]
+    #[command(about = "Run a command in one or more projects")]
+    Exec {
+        #[clap(
+            value_parser = parse_id,
+            help = "IDs of the projects, in decimal or in hex like 0x1A or p1A",
+            required_unless_present = "all",
+            conflicts_with = "all"
+        )]
+        ids: Vec<usize>,
+        #[arg(long, help = "Run in every project matching the filters")]
+        all: bool,
+        #[arg(
+            long = "st",
+            visible_alias = "status",
+            requires = "all",
+            help = "Filter by status. Can be `active`, `paused`, or `archived`"
+        )]
+        status: Vec<Status>,
+        #[arg(long, requires = "all", help = "Filter by tag")]
+        tag: Vec<String>,
+        #[arg(
+            long,
+            requires = "all",
+            help = "Filter by kind, e.g. `rust` or `writing`"
+        )]
+        kind: Vec<String>,
+        #[arg(
+            short,
+            long,
+            default_value = "1",
+            help = "Number of projects to run the command in at the same time"
+        )]
+        jobs: usize,
+        #[arg(long, help = "Stop starting new runs after the first failure")]
+        fail_fast: bool,
+        #[arg(last = true, required = true, help = "Command to run in each project")]
+        command: Vec<String>,
+    },
+    #[command(about = "Manage trashed projects")]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+    #[command(about = "Print or install shell completions")]
+    Completions {
+        #[clap(help = "Shell to generate completions for")]
+        shell: Shell,
+        #[arg(long, help = "Write the completions where the shell loads them from")]
+        install: bool,
+        #[arg(
+            long,
+            conflicts_with = "install",
+            help = "Remove installed completions"
+        )]
+        uninstall: bool,
+    },
+    #[command(about = "Clone GitHub repositories of a user as new projects")]
+    AdoptGh {
+        #[clap(help = "GitHub user whose repositories to adopt")]
+        user: String,
+        #[arg(long, help = "Only adopt repositories with this topic")]
+        topic: Option<String>,
+        #[arg(
+            long,
+            help = "Only adopt repositories whose name matches, `*` is a wildcard"
+        )]
+        include: Option<String>,
+        #[arg(long, help = "Skip repositories whose name matches, `*` is a wildcard")]
+        exclude: Option<String>,
+        #[arg(short, long, help = "Adopt every matching repository without asking")]
+        all: bool,
+        #[arg(long, help = "Print what would be cloned without cloning anything")]
+        dry_run: bool,
+    },
+    #[command(about = "Show project counts, sizes and last activity, by status by default")]
+    Stats {
+        #[arg(long, group = "by", help = "Group the projects by tag")]
+        by_tag: bool,
+        #[arg(long, group = "by", help = "Group the projects by detected kind")]
+        by_kind: bool,
+        #[arg(long, group = "by", help = "Group the projects by creation year")]
+        by_year: bool,
+    },
+    #[command(
+        about = "Add a tag to a project, or manage project tags",
+        args_conflicts_with_subcommands = true,
+        arg_required_else_help = true
+    )]
+    Tag {
+        #[clap(
+            value_parser = parse_id,
+            requires = "tag",
+            help = "ID of the project to tag, in decimal or in hex like 0x1A or p1A"
+        )]
+        id: Option<usize>,
+        #[clap(help = "Tag to add")]
+        tag: Option<String>,
+        #[command(subcommand)]
+        command: Option<TagCommands>,
+    },
+    #[command(about = "Remove a tag from a project, ignoring case")]
+    Untag {
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
         id: usize,
-        #[clap(help = "Name of the template")]
-        name: String,
+        #[clap(help = "Tag to remove")]
+        tag: String,
+    },
+    #[command(about = "Show or change the configuration")]
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
+    },
+    #[command(about = "Check the projects folder for problems")]
+    Doctor {
+        #[arg(
+            long,
+            num_args = 0..=1,
+            help = "Create missing status folders and offer to renumber duplicate ids. \
+                    With a value, also fix projects whose folder date disagrees with their metadata"
+        )]
+        fix: Option<Option<DateFix>>,
+        #[arg(long, help = "Summarize how many projects fail each health check")]
+        projects: bool,
+    },
+    #[command(
+        about = "Create a new template from a project, or manage templates",
+        name = "templates",
+        visible_alias = "template",
+        args_conflicts_with_subcommands = true,
+        subcommand_negates_reqs = true
+    )]
+    Template {
+        #[command(subcommand)]
+        command: Option<TemplateCommands>,
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A", required = true)]
+        id: Option<usize>,
+        #[clap(help = "Name of the template", required = true)]
+        name: Option<String>,
     },
 }
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum TemplateCommands {
-    #[command(about = "List all templates")]
+    #[command(about = "List all templates with their size and number of files")]
     List,
     #[command(about = "Create a new template")]
     New {
         #[clap(help = "Name of the template")]
         name: String,
-        #[clap(help = "ID of the project")]
+        #[clap(value_parser = parse_id, help = "ID of the project, in decimal or in hex like 0x1A or p1A")]
         id: usize,
     },
     #[command(about = "Delete a template")]
@@ -286,62 +1048,419 @@ pub enum TemplateCommands {
         #[clap(help = "Name of the template")]
         name: String,
     },
+    #[command(about = "Rename a template and the projects referencing it")]
+    Rename {
+        #[clap(help = "Current name of the template")]
+        from: String,
+        #[clap(help = "New name of the template")]
+        to: String,
+    },
+    #[command(about = "List the projects created from a template")]
+    UsedBy {
+        #[clap(help = "Name of the template")]
+        name: String,
+    },
+    #[command(about = "List the {{variables}} a template uses")]
+    Vars {
+        #[clap(help = "Name of the template")]
+        name: String,
+    },
+    #[command(about = "Check a template for common issues")]
+    Validate {
+        #[clap(help = "Name of the template")]
+        name: String,
+        #[arg(long, help = "Fix the issues that can be fixed automatically")]
+        fix: bool,
+    },
+    #[command(about = "Write a template to an archive to share it")]
+    Export {
+        #[clap(help = "Name of the template")]
+        name: String,
+        #[arg(short, long, help = "Archive to write, defaults to <NAME>.tar.zst")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Add a template from an archive made by template export")]
+    Import {
+        #[clap(help = "Archive to import")]
+        file: PathBuf,
+        #[arg(long, help = "Name of the template, defaults to its exported name")]
+        name: Option<String>,
+        #[arg(long, help = "Replace an existing template with the same name")]
+        force: bool,
+    },
 }
 
-#[derive(Debug, Clone, Default, ValueEnum)]
-enum Sort {
-    #[default]
-    Id,
-    Name,
-    #[clap(alias = "date")]
-    Created,
-    Accessed,
-    Status,
+#[derive(Debug, Clone, Subcommand)]
+pub enum TrashCommands {
+    #[command(about = "List trashed projects, oldest first")]
+    List,
+    #[command(about = "Delete trashed projects older than trash.retention")]
+    Empty {
+        #[arg(long, help = "Delete every trashed project, regardless of its age")]
+        all: bool,
+    },
 }
 
-#[derive(Debug, Clone, Subcommand, Default)]
-enum InitShells {
-    #[default]
-    #[command(about = "Init fish shell")]
-    Fish,
-    #[command(about = "Init zsh shell")]
+#[derive(Debug, Clone, Subcommand)]
+pub enum TagCommands {
+    #[command(about = "Show all tags and how many projects use them")]
+    Stats {
+        #[arg(
+            long,
+            default_value = "1",
+            help = "Only show tags used by at least this many projects"
+        )]
+        min_count: usize,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommands {
+    #[command(about = "Print the resolved configuration and where each value comes from")]
+    Show,
+    #[command(about = "Print where vpm keeps its config, cache and state")]
+    Paths,
+    #[command(about = "Print the resolved value of a key")]
+    Get {
+        #[clap(help = "Key to print")]
+        key: String,
+    },
+    #[command(about = "Set a key in the config file")]
+    Set {
+        #[clap(help = "Key to set")]
+        key: String,
+        #[clap(help = "New value of the key")]
+        value: String,
+    },
+    #[command(about = "Create or update the config file by answering a few questions")]
+    Init,
+}
+
+#[derive(Debug, Clone, Subcommand, Default)]
+enum InitShells {
+    #[default]
+    #[command(about = "Init fish shell")]
+    Fish,
+    #[command(about = "Init zsh shell")]
     Zsh,
+    #[command(about = "Init bash shell")]
+    Bash,
+    #[command(about = "Init nushell", alias = "nu")]
+    Nushell,
+}
+
+impl Commands {
+    /// Whether the command can print its output, and then its errors, as JSON.
+    fn supports_json(&self) -> bool {
+        matches!(
+            self,
+            Commands::Status { .. }
+                | Commands::Path { .. }
+                | Commands::Peek { .. }
+                | Commands::Stats { .. }
+                | Commands::Info { .. }
+                | Commands::List { .. }
+                | Commands::Search { .. }
+                | Commands::Tag {
+                    command: Some(TagCommands::Stats { .. }),
+                    ..
+                }
+                | Commands::New { .. }
+                | Commands::Rename { edit: false, .. }
+                | Commands::Duplicate { .. }
+        )
+    }
+
+    /// Whether the command changes projects, which is when vpm looks at the trash.
+    fn mutates(&self) -> bool {
+        matches!(
+            self,
+            Commands::New { .. }
+                | Commands::Status {
+                    status: Some(_),
+                    ..
+                }
+                | Commands::Archive { .. }
+                | Commands::Pause { .. }
+                | Commands::Resume { .. }
+                | Commands::Rename { .. }
+                | Commands::Delete { .. }
+                | Commands::Duplicate { .. }
+                | Commands::Move { .. }
+                | Commands::AdoptGh { .. }
+        )
+    }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = Args::parse();
-    let path_str = match env::var("PROJECT_HOME") {
-        Ok(path) => path,
-        Err(_) => {
-            return Err(anyhow!(
-                "You must set the $PROJECT_HOME variable to the root of your projects folder!"
-            ));
+    let json = args.json_output;
+    if json && !args.command.as_ref().is_some_and(Commands::supports_json) {
+        Args::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--json only applies to list, search, path, status, peek, stats, info, tag stats, new, \
+                 rename and duplicate",
+            )
+            .exit();
+    }
+    let exit_code = |err: &color_eyre::eyre::Report| {
+        err.downcast_ref::<VpmError>()
+            .map_or(1, VpmError::exit_code)
+    };
+    match run(args) {
+        Err(err) if json => {
+            json::print(error::to_json(&err));
+            process::exit(exit_code(&err));
+        }
+        Err(err) if exit_code(&err) != 1 => {
+            eprintln!("Error: {err:?}");
+            process::exit(exit_code(&err));
         }
+        result => result,
+    }
+}
+
+fn run(mut args: Args) -> Result<()> {
+    Paths::migrate()?;
+    let paths = Paths::resolve()?;
+    let mut config = Config::load(&paths)?;
+    if let Some(project_home) = &args.project_home {
+        config.project_home = Setting {
+            value: Some(project_home.clone()),
+            source: Source::Flag("--project-home"),
+        };
+    }
+    args.color = display::should_use_color(&args, config.color()?);
+    for flag in config.display_flags()? {
+        match flag {
+            "id" => args.id = true,
+            "path" => args.path = true,
+            "date" => args.date = true,
+            "full-name" => args.full_name = true,
+            "no-name" => args.no_name = true,
+            "accessed" => args.accessed = true,
+            "status" => args.status = true,
+            "show-kind" => args.show_kind = true,
+            "description" => args.show_description = true,
+            _ => unreachable!("display flags are checked against config::DISPLAY_FLAGS"),
+        }
+    }
+    if let Some(Commands::Config { command }) = &args.command {
+        return run_config(
+            &config,
+            &paths,
+            command.as_ref().unwrap_or(&ConfigCommands::Show),
+        );
+    }
+    if let Some(Commands::Completions {
+        shell,
+        install,
+        uninstall,
+    }) = &args.command
+    {
+        let mut script = Vec::new();
+        clap_complete::generate(*shell, &mut Args::command(), "vpm", &mut script);
+        let script = String::from_utf8(script)?;
+        return match (install, uninstall) {
+            (true, _) => shells::install_completions(*shell, &script),
+            (_, true) => shells::uninstall_completions(*shell),
+            _ => {
+                print!("{script}");
+                Ok(())
+            }
+        };
+    }
+    let path_str = match &config.project_home.value {
+        Some(path) => home::expand_tilde(path),
+        None => return Err(VpmError::ProjectHomeNotSet.into()),
     };
+    // Symlinks are only resolved for comparisons, printed paths keep the root as configured.
+    let canonical_root = fs::canonicalize(&path_str)
+        .map_err(|err| anyhow!("Could not open the project home {path_str}: {err}"))?;
+    home::set_mode(if args.flat {
+        LayoutMode::Flat
+    } else {
+        LayoutMode::detect(&path_str)
+    });
+
+    if let Some(Commands::ProjectCount {
+        status,
+        format,
+        cached,
+    }) = &args.command
+    {
+        let statuses = match status {
+            Some(status) => vec![*status],
+            None => vec![Status::Active, Status::Paused, Status::Archived],
+        };
+        let count: usize = if *cached {
+            let counts = index::load().counts.remove(&path_str).unwrap_or_default();
+            statuses
+                .iter()
+                .filter_map(|status| counts.get(&status.to_string()))
+                .sum()
+        } else {
+            statuses
+                .iter()
+                .map(|status| count_projects(&path_str, *status))
+                .sum()
+        };
+        match format {
+            Some(format) => println!("{}", format.replace("{count}", &count.to_string())),
+            None => println!("{count}"),
+        }
+        return Ok(());
+    }
 
-    let mut projects = read_files(&path_str, &args);
+    let mut display_args = args.clone();
+    if let Some(Commands::List {
+        accessed_col: true, ..
+    }) = args.command
+    {
+        display_args.accessed = true;
+    }
+    // Doctor lists the folders it skips itself.
+    if let Some(Commands::Doctor { .. }) = args.command {
+        display_args.quiet = true;
+    }
+    let mut projects = read_files(&path_str, &canonical_root, &display_args);
+    home::set_id_width(
+        projects
+            .values()
+            .map(|project| project.id_width.max(home::hex_width(project.id)))
+            .max()
+            .unwrap_or(0),
+    );
+    index::record_counts(&path_str, projects.values());
+    let json = args.json_output;
+    if !args.quiet && !json && io::stderr().is_terminal() {
+        let scan = home::scan();
+        if let Some(hint) =
+            home::naming_hint(scan.parsed, scan.skipped.len(), config.scan_max_skipped()?)
+        {
+            eprintln!("Hint: {hint}");
+        }
+    }
+    if args.command.as_ref().is_some_and(Commands::mutates) {
+        if let Err(err) = check_trash(&config, &path_str, json) {
+            eprintln!("Warning: could not check the trash: {err}");
+        }
+    }
+    match &mut args.command {
+        Some(Commands::Pause { ids, pick: true }) => {
+            *ids = pick_ids(&projects, Some(Status::Paused))?
+        }
+        Some(Commands::Archive {
+            ids, pick: true, ..
+        }) => *ids = pick_ids(&projects, Some(Status::Archived))?,
+        Some(Commands::Resume { ids, pick: true }) => {
+            *ids = pick_ids(&projects, Some(Status::Active))?
+        }
+        Some(Commands::Delete {
+            ids, pick: true, ..
+        }) => *ids = pick_ids(&projects, None)?,
+        _ => {}
+    }
     match args.command {
         Some(Commands::List {
             sort,
             reverse,
             limit,
             status,
+            only_recent_n,
+            no_truncate,
+            kind,
+            since_id,
+            before_id,
+            name_contains,
+            name_regex,
+            case_sensitive,
+            since,
+            until,
+            with_notes,
+            without_notes,
+            format_shell_all,
+            table,
+            ..
         }) => {
-            projects
-                .values()
+            let today = when::today();
+            let since = since.map(|since| since.first_day(today));
+            let until = until.map(|until| until.last_day(today));
+            let name_regex = match (name_regex, name_contains) {
+                (Some(pattern), _) => Some(pattern),
+                (None, Some(text)) => Some(regex::escape(&text)),
+                (None, None) => None,
+            }
+            .map(|pattern| {
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|err| anyhow!("Invalid --name-regex `{pattern}`: {err}"))
+            })
+            .transpose()?;
+            if args.show_kind || !kind.is_empty() {
+                kind::resolve_kinds(&mut projects, &config.kind_rules)?;
+            }
+            if args.health {
+                health::resolve_health(&mut projects, &config.health_checks)?;
+            }
+            let width = row_width(!no_truncate && !args.path);
+            let in_range = match (since_id, before_id) {
+                (Some(since), Some(before)) if since >= before => Vec::new(),
+                _ => projects
+                    .range((
+                        since_id.map_or(Bound::Unbounded, Bound::Excluded),
+                        before_id.map_or(Bound::Unbounded, Bound::Excluded),
+                    ))
+                    .map(|(_, project)| project)
+                    .collect_vec(),
+            };
+            let filtered = in_range
+                .into_iter()
                 .filter(|p| status.is_empty() || status.contains(&p.status))
-                .sorted_by(|a, b| {
-                    let mut ordering = Ordering::Equal;
-                    for sort_order in sort.iter() {
-                        ordering = ordering.then(match sort_order {
-                            Sort::Id => a.id.cmp(&b.id),
-                            Sort::Name => a.name.cmp(&b.name),
-                            Sort::Created => a.date.cmp(&b.date),
-                            Sort::Accessed => a.last_accessed.cmp(&b.last_accessed),
-                            Sort::Status => a.status.cmp(&b.status),
-                        });
+                .filter(|p| kind.is_empty() || p.kind.as_ref().is_some_and(|k| kind.contains(k)))
+                .filter(|p| {
+                    name_regex
+                        .as_ref()
+                        .is_none_or(|regex| regex.is_match(&p.name))
+                })
+                .filter(|p| since.is_none_or(|since| p.date >= since))
+                .filter(|p| until.is_none_or(|until| p.date <= until))
+                .filter(|p| {
+                    if !with_notes && !without_notes {
+                        return true;
                     }
+                    let has_notes = fs::metadata(meta::notes_path(p.get_path()))
+                        .is_ok_and(|metadata| metadata.len() > 0);
+                    has_notes == with_notes
+                })
+                .collect_vec();
+            let filtered = match only_recent_n {
+                Some(n) => filtered
+                    .into_iter()
+                    .into_group_map_by(|p| p.status)
+                    .into_values()
+                    .flat_map(|group| {
+                        group
+                            .into_iter()
+                            .sorted_by(|a, b| {
+                                b.last_accessed.cmp(&a.last_accessed).then(a.id.cmp(&b.id))
+                            })
+                            .take(n)
+                    })
+                    .collect_vec(),
+                None => filtered,
+            };
+            let sort = match sort.is_empty() {
+                true => config.list_sort()?,
+                false => sort,
+            };
+            let sorted = filtered
+                .into_iter()
+                .sorted_by(|a, b| {
+                    let ordering = sort::compare(a, b, &sort);
                     if reverse {
                         ordering.reverse()
                     } else {
@@ -349,220 +1468,1968 @@ fn main() -> Result<()> {
                     }
                 })
                 .take(if limit > 0 { limit } else { usize::MAX })
+                .collect_vec();
+            if json {
+                json::print(json::projects(sorted));
+                return Ok(());
+            }
+            if table && io::stdout().is_terminal() {
+                // Optional columns, in the order the plain rows print them, with the width
+                // numeric columns are right-aligned to.
+                type Cell = fn(&Project) -> String;
+                let optional: [(bool, &str, usize, Cell); 5] = [
+                    (args.show_kind, "KIND", 0, |project| {
+                        project.kind.clone().unwrap_or_else(|| "-".to_string())
+                    }),
+                    (args.age_column, "AGE", 10, |project| {
+                        format!("{:>10}", human_age(project.age_days()))
+                    }),
+                    (args.days_since_access_column, "IDLE", 5, |project| {
+                        format!("{:>5}", format!("{}d", project.days_since_access()))
+                    }),
+                    (args.health, "HEALTH", 0, |project| {
+                        let flags = project.health.as_ref().map(Health::flags);
+                        flags.unwrap_or_else(|| "-".to_string())
+                    }),
+                    (args.show_description, "DESCRIPTION", 0, |project| {
+                        let description = project.description.as_deref().unwrap_or_default();
+                        description.lines().next().unwrap_or("-").to_string()
+                    }),
+                ];
+                let optional = optional
+                    .into_iter()
+                    .filter(|(shown, ..)| *shown)
+                    .collect_vec();
+                let rows = sorted
+                    .iter()
+                    .map(|project| {
+                        let status = project.status.to_string();
+                        let mut row = vec![
+                            project.id.to_string(),
+                            project.name.clone(),
+                            if args.color {
+                                display::status_color(&status, project.status)
+                            } else {
+                                status
+                            },
+                            project.date.to_string(),
+                            project.last_accessed.format("%Y-%m-%d %H:%M").to_string(),
+                        ];
+                        row.extend(optional.iter().map(|(.., cell)| cell(project)));
+                        row
+                    })
+                    .collect_vec();
+                let headers = ["ID", "NAME", "STATUS", "CREATED", "ACCESSED"]
+                    .into_iter()
+                    .map(str::to_string)
+                    .chain(
+                        optional
+                            .iter()
+                            .map(|(_, header, width, _)| format!("{header:>width$}")),
+                    )
+                    .collect_vec();
+                let headers = headers.iter().map(String::as_str).collect_vec();
+                for line in layout::table(&headers, &rows, 1, width) {
+                    println!("{line}");
+                }
+                return Ok(());
+            }
+            sorted.into_iter().for_each(|project| {
+                if format_shell_all {
+                    println!(
+                        "{}",
+                        shells::export_line(&project.name, &project.get_path())
+                    );
+                    return;
+                }
+                let row = project.to_string();
+                match width {
+                    Some(width) => println!(
+                        "{}",
+                        layout::fit_row(&row, display_args.name_column(), width)
+                    ),
+                    None => println!("{}", row),
+                }
+            });
+        }
+        Some(Commands::Recent { n }) => {
+            let width = row_width(!args.path);
+            projects
+                .values()
+                .sorted_by(|a, b| b.last_accessed.cmp(&a.last_accessed).then(a.id.cmp(&b.id)))
+                .take(n)
                 .for_each(|project| {
-                    println!("{}", project);
+                    let row = project.to_string();
+                    match width {
+                        Some(width) => println!(
+                            "{}",
+                            layout::fit_row(&row, display_args.name_column(), width)
+                        ),
+                        None => println!("{}", row),
+                    }
                 });
         }
         Some(Commands::New {
             ref name,
+            from_clipboard,
             ref template,
+            priority,
+            echo_id,
+            echo_path,
+            echo_full_name,
+            no_template,
+            open_split,
+            open_tmux,
+            tmux_split,
         }) => {
-            let id = projects
-                .last_key_value()
-                .map(|kv| kv.0 + 1)
-                .unwrap_or_default();
-            let date = Local::now().date_naive();
-            let name = format_name(name).unwrap();
-            let project = Project::new(id, name, date, Local::now()).with_args(&args);
-            match template {
-                Some(template) => {
-                    let template_path = Path::new(&path_str).join("templates").join(&template);
-                    if !template_path.exists() {
-                        return Err(anyhow!("Template does not exist!"));
+            let template = match template {
+                Some(template) if template == "?" => {
+                    let names = template::names(&path_str)?;
+                    Some(names[pick::select_one("Template", &names)?].clone())
+                }
+                Some(template) => Some(template.clone()),
+                None if no_template => None,
+                None => env::var("VPM_DEFAULT_TEMPLATE")
+                    .ok()
+                    .filter(|template| !template.is_empty())
+                    .filter(|template| {
+                        let exists = template::template_path(&path_str, template).is_dir();
+                        if !exists {
+                            eprintln!(
+                                "Warning: $VPM_DEFAULT_TEMPLATE template {template} does not exist, creating an empty project"
+                            );
+                        }
+                        exists
+                    }),
+            };
+            let name = if from_clipboard {
+                let text = clipboard::read_from_clipboard()?;
+                match format_name(&text) {
+                    Ok(name) if !name.is_empty() && !name.contains('/') => name,
+                    _ => {
+                        return Err(anyhow!(
+                            "The clipboard does not contain a valid project name: {:?}",
+                            text.trim()
+                        ))
                     }
-                    Command::new("cp")
-                        .arg("-r")
-                        .arg(template_path)
-                        .arg(project.get_path())
-                        .output()
-                        .unwrap();
-                }
-                None => {
-                    Command::new("mkdir")
-                        .arg(project.get_path())
-                        .output()
-                        .unwrap();
-                }
-            }
-            println!("{}", &project);
-        }
-        Some(Commands::Rename { id, name }) => {
-            let project = projects.get(&id).unwrap();
-            let new_name = format_name(&name).unwrap();
-            let new_project =
-                Project::new(id, new_name, project.date, Local::now()).with_status(project.status);
-            fs::rename(project.get_path(), new_project.get_path())?;
-            println!("Renamed project: {}", &new_project);
-        }
-        Some(Commands::Path { id }) => {
-            let project = projects
-                .get(&id)
-                .ok_or(anyhow!("Project {id} not found!"))?;
-            println!("{}", project.get_path());
+                }
+            } else {
+                name.clone()
+                    .expect("clap requires a name without --from-clipboard")
+            };
+            let mut name = name;
+            let plan = loop {
+                let plan =
+                    create::plan_new(&projects, &path_str, &name, template.as_deref(), priority);
+                let path = match plan.as_ref().map_err(|err| err.downcast_ref()) {
+                    Err(Some(VpmError::PathExists { path }))
+                        if io::stdin().is_terminal() && !json =>
+                    {
+                        path.clone()
+                    }
+                    _ => break plan?,
+                };
+                match ask_collision(&path)? {
+                    create::Resolution::Rename(new_name) => name = new_name,
+                    create::Resolution::Suffix => {
+                        break create::plan_suffixed(
+                            &projects,
+                            &path_str,
+                            &name,
+                            template.as_deref(),
+                            priority,
+                        )?
+                    }
+                    create::Resolution::Adopt => {
+                        if let Some(template) = &template {
+                            eprintln!("Warning: the template {template} is not applied to an adopted folder");
+                        }
+                        break create::plan_adopt(&projects, &path_str, &name, priority)?;
+                    }
+                    create::Resolution::Abort => {
+                        println!("Aborting...");
+                        return Ok(());
+                    }
+                }
+            };
+            create::execute(&plan)?;
+            let project = plan.project.with_args(&args);
+            hint_id_width(&project, args.quiet);
+            if json {
+                json::print(json::versioned(json::project(&project)));
+            } else if echo_id {
+                println!("{}", project.id);
+            } else if echo_path {
+                println!("{}", project.get_path());
+            } else if echo_full_name {
+                println!("{}", project.full_name());
+            } else {
+                println!("{}", &project);
+            }
+            if open_split {
+                let path = project.get_path();
+                let notes = meta::notes_path(&path);
+                if let Some(parent) = notes.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                if !notes.exists() {
+                    fs::File::create(&notes)?;
+                }
+                let command = config.split_command(&path, &notes.to_string_lossy());
+                let (program, split_args) = command
+                    .split_first()
+                    .ok_or(anyhow!("editor.split_command is empty!"))?;
+                Command::new(program)
+                    .args(split_args)
+                    .status()
+                    .map_err(|err| anyhow!("Failed to run `{program}`: {err}"))?;
+            }
+            if open_tmux {
+                terminal::open_in_tmux(&project.get_path(), &project.name, tmux_split)?;
+            }
         }
-        Some(Commands::Code { id }) => {
-            let project = projects
-                .get(&id)
-                .ok_or(anyhow!("Project {id} not found!"))?;
+        Some(Commands::Move { id, status }) => {
+            if let Some(project) = projects.get(&id).filter(|p| p.status == status) {
+                return Err(anyhow!("{} is already {status}", project.full_name()));
+            }
+            move_project(&mut projects, id, status, None)?
+        }
+        Some(Commands::Duplicate { id, name }) => {
+            let source = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            let project = create::duplicate(&projects, source, &name)?;
+            if json {
+                json::print(json::versioned(json::project(&project)));
+            } else {
+                println!("{}", project.full_name());
+            }
+            hint_id_width(&project, args.quiet);
+        }
+        Some(Commands::Meta {
+            id,
+            description,
+            tag,
+        }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
             let path = project.get_path();
-            Command::new("code")
-                .arg(path)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .unwrap();
+            let project_meta = if description.is_none() && tag.is_empty() {
+                meta::load(&path)?.unwrap_or_default()
+            } else {
+                meta::update(&path, |project_meta| {
+                    if let Some(description) = description {
+                        project_meta.description = description;
+                    }
+                    for tag in tag {
+                        if !project_meta
+                            .tags
+                            .iter()
+                            .any(|t| t.eq_ignore_ascii_case(&tag))
+                        {
+                            project_meta.tags.push(tag);
+                        }
+                    }
+                })?
+            };
+            println!("description: {}", project_meta.description);
+            println!("tags: {}", project_meta.tags.join(", "));
+        }
+        Some(Commands::Describe { id, description }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            let description = match description.as_str() {
+                "-" => {
+                    let mut text = String::new();
+                    io::stdin().read_to_string(&mut text)?;
+                    text.trim_end().to_string()
+                }
+                _ => description,
+            };
+            meta::set_description(project.get_path(), &description)?;
+            println!("{description}");
+        }
+        Some(Commands::GitInit {
+            id,
+            template,
+            gitignore,
+            initial_commit,
+        }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            let path = PathBuf::from(project.get_path());
+            git::init(&path, template.as_deref())?;
+            println!("Initialized a git repository in {}", path.display());
+            if let Some(source) = gitignore.or_else(|| config.gitignore.value.clone()) {
+                let target = path.join(".gitignore");
+                if target.exists() {
+                    println!("Kept the existing .gitignore");
+                } else {
+                    let source = home::expand_tilde(&source);
+                    fs::copy(&source, &target)
+                        .map_err(|err| anyhow!("Could not copy {source} to .gitignore: {err}"))?;
+                    println!("Copied {source} to .gitignore");
+                }
+            }
+            if initial_commit {
+                git::initial_commit(&path)?;
+                println!("Committed the project as `Initial commit`");
+            }
+        }
+        Some(Commands::Open { id }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            opener::open_folder(Path::new(&project.get_path()))?;
+        }
+        Some(Commands::Migrate { force }) => {
+            let width = home::id_width();
+            let renames = migration_plan(&projects, width);
+            if renames.is_empty() {
+                println!("All project folders already use {width} hex digits");
+                return Ok(());
+            }
+            for (from, to) in renames.iter() {
+                println!("{} -> {}", from.full_name(), to.full_name());
+            }
+            if !force && !confirm(&format!("Rename {} project folders", renames.len()))? {
+                println!("Aborting...");
+                return Ok(());
+            }
+            for (from, to) in renames.iter() {
+                rename_project(from, to)?;
+            }
+            println!(
+                "Renamed {} project folders to {width} hex digits",
+                renames.len()
+            );
+        }
+        Some(Commands::Delete { ids, force, .. }) => {
+            let doomed = ids
+                .iter()
+                .unique()
+                .map(|&id| projects.get(&id).ok_or(VpmError::ProjectNotFound { id }))
+                .collect::<Result<Vec<_>, _>>()?;
+            if !force {
+                for project in doomed.iter() {
+                    println!("{}", project.get_path());
+                }
+                let question = match doomed.as_slice() {
+                    [project] => format!("Delete {} permanently", project.full_name()),
+                    _ => format!("Delete {} projects permanently", doomed.len()),
+                };
+                if !confirm(&question)? {
+                    println!("Aborting...");
+                    return Ok(());
+                }
+            }
+            for project in doomed {
+                let path = project.get_path();
+                fs::remove_dir_all(&path)
+                    .map_err(|err| anyhow!("Failed to delete {path}: {err}"))?;
+                println!("Deleted {}", project.full_name());
+            }
+        }
+        Some(Commands::Rename { edit: true, .. }) => rename::edit(&projects)?,
+        Some(Commands::Rename {
+            id: Some(selector),
+            name: Some(name),
+            fuzzy,
+            ..
+        }) => {
+            let id = resolve_id(&selector, &projects, fuzzy)?;
+            let project = &projects[&id];
+            let new_name = format_name(&name).map_err(|reason| VpmError::InvalidName {
+                name: name.clone(),
+                reason,
+            })?;
+            if new_name == project.name {
+                return Err(anyhow!(
+                    "{} is already named {new_name}",
+                    project.full_name()
+                ));
+            }
+            let new_project = Project::new(&project.root, id, new_name, project.date, Local::now())
+                .with_status(project.status)
+                .with_id_width(project.id_width);
+            rename_project(project, &new_project)?;
+            if json {
+                json::print(json::versioned(json::project(&new_project)));
+            } else {
+                println!("Renamed project: {}", &new_project);
+            }
+        }
+        Some(Commands::Info { id, no_size }) => {
+            health::resolve_health(&mut projects, &config.health_checks)?;
+            let Some(project) = projects.get(&id) else {
+                // An exported project is gone from the project home, say where it went instead.
+                let tombstone =
+                    tombstone::find(&paths, id)?.ok_or(VpmError::ProjectNotFound { id })?;
+                if json {
+                    json::print(json::versioned(
+                        serde_json::json!({ "tombstone": tombstone }),
+                    ));
+                } else {
+                    print!("{}: ", tombstone.full_name);
+                    print_tombstone(&tombstone);
+                }
+                return Ok(());
+            };
+            if args.path && !json {
+                println!("{}", project.get_path());
+                return Ok(());
+            }
+            let size = (!no_size).then(|| size::dir_size(project.get_path()));
+            let project_meta = meta::load(project.get_path())?.unwrap_or_default();
+            if json {
+                let mut object = json::project(project);
+                object["accessed_source"] = project.accessed_source.to_string().into();
+                object["size"] = size.into();
+                object["priority"] = project_meta.priority.into();
+                object["template"] = project_meta.template.into();
+                json::print(json::versioned(object));
+                return Ok(());
+            }
+            println!("name:    {}", project.name);
+            println!("folder:  {}", project.full_name());
+            println!("path:    {}", project.get_path());
+            println!("status:  {}", project.status);
+            println!("created: {}", project.date);
+            println!(
+                "accessed: {} (from {})",
+                project.last_accessed.format("%Y-%m-%d %H:%M"),
+                project.accessed_source
+            );
+            if let Some(size) = size {
+                println!("size:    {}", size::format_size(size));
+            }
+            if let Some(priority) = project_meta.priority {
+                println!("priority: {priority}");
+            }
+            if let Some(name) = project_meta.template {
+                let deleted = if template::template_path(&path_str, &name).is_dir() {
+                    ""
+                } else {
+                    " (deleted)"
+                };
+                println!("template: {name}{deleted}");
+            }
+            if let Some(health) = &project.health {
+                println!("health:  {}", health.flags());
+                for result in health.results.iter() {
+                    let state = if result.passed { "yes" } else { "missing" };
+                    println!("  {:12} {}", result.name, state);
+                }
+            }
+        }
+        Some(Commands::Peek { id, depth }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            let entries = peek::peek(project.get_path(), depth.max(1))?;
+            if json {
+                json::print(json::versioned(serde_json::json!({ "entries": entries })));
+            } else {
+                println!("{}/", project.full_name());
+                for line in peek::render(&entries) {
+                    println!("{line}");
+                }
+            }
+        }
+        Some(Commands::Path { id, format_shell }) => {
+            let id = resolve_best_id(&id, &projects)?;
+            let project = &projects[&id];
+            let path = project.get_path();
+            if json {
+                json::print(json::versioned({
+                    let mut object = json::project(project);
+                    object["exists"] = Path::new(&path).exists().into();
+                    object
+                }));
+            } else if format_shell {
+                println!("{}", shells::export_line(&project.name, &path));
+            } else {
+                println!("{}", path);
+            }
+        }
+        Some(Commands::Code {
+            id,
+            zed,
+            terminal,
+            profile,
+        }) => {
+            let id = resolve_best_id(&id, &projects)?;
+            let project = &projects[&id];
+            let path = project.get_path();
+            let editor = match (zed, &args.editor) {
+                (true, _) => "zed",
+                (_, Some(editor)) => editor,
+                _ => config.editor(),
+            };
+            let extra = match profile {
+                Some(profile) => vec!["--profile".to_string(), profile],
+                None => Vec::new(),
+            };
+            editor::open(editor, &extra, &path, terminal)?;
+        }
+        Some(Commands::Exec {
+            ids,
+            all,
+            status,
+            tag,
+            kind,
+            jobs,
+            fail_fast,
+            command,
+        }) => {
+            if !kind.is_empty() {
+                kind::resolve_kinds(&mut projects, &config.kind_rules)?;
+            }
+            let selected = if all {
+                let mut selected = Vec::new();
+                for project in projects.values() {
+                    if !status.is_empty() && !status.contains(&project.status) {
+                        continue;
+                    }
+                    if !kind.is_empty() && !project.kind.as_ref().is_some_and(|k| kind.contains(k))
+                    {
+                        continue;
+                    }
+                    if !tag.is_empty() {
+                        let tags = project.meta.as_ref().map(|meta| &meta.tags);
+                        if !tags.is_some_and(|tags| tag.iter().any(|tag| tags.contains(tag))) {
+                            continue;
+                        }
+                    }
+                    selected.push(project);
+                }
+                selected
+            } else {
+                ids.iter()
+                    .map(|&id| projects.get(&id).ok_or(VpmError::ProjectNotFound { id }))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            if selected.is_empty() {
+                println!("No projects match");
+                return Ok(());
+            }
+            let stop = Arc::new(AtomicBool::new(false));
+            let interrupted = Arc::clone(&stop);
+            // The children get the terminal's SIGINT themselves, vpm only stops starting new ones.
+            ctrlc::set_handler(move || interrupted.store(true, Ordering::SeqCst))?;
+            let outcomes = exec::run_all(&selected, &command, jobs, fail_fast, &stop);
+            println!();
+            for line in exec::summary(&selected, &outcomes) {
+                println!("{line}");
+            }
+            let failed = outcomes
+                .iter()
+                .filter(|outcome| outcome.is_failure())
+                .count();
+            if failed > 0 {
+                return Err(anyhow!("{failed} of {} projects failed", selected.len()));
+            }
+        }
+        Some(Commands::Trash {
+            command: TrashCommands::List,
+        }) => {
+            for item in trash::list(&path_str)? {
+                println!(
+                    "{}\t{}",
+                    item.trashed_at.format("%Y-%m-%d %H:%M"),
+                    item.name
+                );
+            }
+        }
+        Some(Commands::Trash {
+            command: TrashCommands::Empty { all },
+        }) => {
+            let mut items = trash::list(&path_str)?;
+            if !all {
+                items = trash::expired(items, config.trash_retention()?, when::today());
+            }
+            if items.is_empty() {
+                println!("Nothing to delete");
+                return Ok(());
+            }
+            for item in &items {
+                println!("{}", item.name);
+            }
+            if !confirm(&format!(
+                "Permanently delete these {} trashed projects",
+                items.len()
+            ))? {
+                println!("Aborting...");
+                return Ok(());
+            }
+            trash::empty(&items)?;
+            println!("Deleted {} trashed projects", items.len());
         }
         Some(Commands::Search {
             pattern,
             limit,
             status,
+            tag,
+            fallback_list,
+            search_full_name,
+            min_score,
+            in_description,
+            ..
         }) => {
+            let pattern = match pattern {
+                Some(pattern) => pattern,
+                None if tag.is_some() => String::new(),
+                None if !io::stdin().is_terminal() => {
+                    let mut line = String::new();
+                    io::stdin().read_line(&mut line)?;
+                    line.trim().to_string()
+                }
+                None => return Err(anyhow!("Pattern required")),
+            };
             let matcher = SkimMatcherV2::default();
-            projects
+            let descriptions = if in_description {
+                projects
+                    .values()
+                    .map(|project| (project.id, project.description.clone().unwrap_or_default()))
+                    .collect::<BTreeMap<_, _>>()
+            } else {
+                BTreeMap::new()
+            };
+            let tagged = tag.as_ref().map(|tag| {
+                projects
+                    .values()
+                    .filter(|project| {
+                        project.meta.as_ref().is_some_and(|meta| {
+                            meta.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+                        })
+                    })
+                    .map(|project| project.id)
+                    .collect::<BTreeSet<_>>()
+            });
+            let matches = projects
                 .values()
                 .filter_map(|project| {
-                    if !(status.is_empty()) || status.contains(&project.status) {
+                    if !status.is_empty() && !status.contains(&project.status) {
+                        return None;
+                    }
+                    if tagged
+                        .as_ref()
+                        .is_some_and(|tagged| !tagged.contains(&project.id))
+                    {
                         return None;
                     }
-                    let score = matcher.fuzzy_match(&project.to_string(), &pattern);
-                    score.map(|score| (project, score))
+                    let score = |haystack: &str, weight: f64| {
+                        matcher
+                            .fuzzy_match(haystack, &pattern)
+                            .map(|score| (score as f64 * weight).round() as i64)
+                    };
+                    // The name alone, never the displayed line, which `-p` or `-d` would fill with
+                    // paths and dates that everything matches.
+                    let (name, full_name) = match (search_full_name, in_description) {
+                        (true, true) => (Some(project.name.as_str()), Some(project.full_name())),
+                        (true, false) => (None, Some(project.full_name())),
+                        (false, _) => (Some(project.name.as_str()), None),
+                    };
+                    let description = descriptions
+                        .get(&project.id)
+                        .filter(|description| !description.is_empty());
+                    [
+                        name.and_then(|name| score(name, 1.0)),
+                        full_name.and_then(|full_name| score(&full_name, 0.9)),
+                        description.and_then(|description| score(description, 0.8)),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .max()
+                    .filter(|score| *score >= min_score)
+                    .map(|score| (project, score))
                 })
                 .sorted_by(|(_, score1), (_, score2)| score2.cmp(score1))
                 .take(if limit > 0 { limit } else { usize::MAX })
-                .for_each(|(project, _)| {
+                .collect_vec();
+            let matches = if matches.is_empty() && fallback_list {
+                eprintln!("No matches found; showing recent projects:");
+                projects
+                    .values()
+                    .sorted_by(|a, b| b.last_accessed.cmp(&a.last_accessed).then(a.id.cmp(&b.id)))
+                    .take(if limit > 0 { limit } else { usize::MAX })
+                    .collect_vec()
+            } else {
+                matches
+                    .into_iter()
+                    .map(|(project, _)| project)
+                    .collect_vec()
+            };
+            if json {
+                json::print(json::projects(matches));
+            } else {
+                matches.iter().for_each(|project| {
                     println!("{project}");
                 });
+            }
         }
         Some(Commands::Init { shell }) => init_shell(shell)?,
-        Some(Commands::Template { name, id }) => {
-            let project = projects.get(&id).unwrap();
-            let project_path = project.get_path();
-            let templates_root = Path::new(&path_str).join("templates");
-            if !templates_root.exists() {
-                Command::new("mkdir").arg(&templates_root).output().unwrap();
-            }
-            let template_path = templates_root.join(&name);
-            Command::new("cp")
-                .arg("-r")
-                .arg(project_path)
-                .arg(template_path)
-                .output()
-                .unwrap();
-        }
-        Some(Commands::Status { id }) => {
-            let project = projects.get(&id).unwrap();
-            println!("{}", project.status);
-        }
-        Some(Commands::Archive { id }) => {
-            let project = projects.get_mut(&id).unwrap();
-            project.set_status(Status::Archived)?;
-            println!("{}", project);
-        }
-        Some(Commands::Pause { id }) => {
-            let project = projects.get_mut(&id).unwrap();
-            project.set_status(Status::Paused)?;
-            println!("{}", project);
-        }
-        Some(Commands::Resume { id }) => {
-            let project = projects.get_mut(&id).unwrap();
-            project.set_status(Status::Active)?;
-            println!("{}", project);
+        Some(Commands::Template {
+            command: None,
+            id: Some(id),
+            name: Some(name),
+        })
+        | Some(Commands::Template {
+            command: Some(TemplateCommands::New { name, id }),
+            ..
+        }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            template::capture(&path_str, project.get_path(), &name)?;
+            println!("Created template {name} from {}", project.full_name());
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::List),
+            ..
+        }) => {
+            let rows = template::names(&path_str)?
+                .into_iter()
+                .map(|name| {
+                    let template_path = template::template_path(&path_str, &name);
+                    let size = size::format_size(size::dir_size(&template_path));
+                    (name, size, size::file_count(&template_path))
+                })
+                .collect_vec();
+            let name_width = rows.iter().map(|(name, ..)| name.len()).max().unwrap_or(0);
+            let size_width = rows
+                .iter()
+                .map(|(_, size, _)| size.len())
+                .max()
+                .unwrap_or(0);
+            for (name, size, files) in rows {
+                let plural = if files == 1 { "" } else { "s" };
+                println!("{name:name_width$}  {size:>size_width$}  {files} file{plural}");
+            }
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::UsedBy { name }),
+            ..
+        }) => {
+            for id in template::users(&projects, &name)? {
+                println!("{}", projects[&id]);
+            }
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::Vars { name }),
+            ..
+        }) => {
+            let uses = template::variables(&path_str, &name)?;
+            if uses.is_empty() {
+                println!("Template {name} uses no variables");
+            }
+            for (variable, VariableUse { count, sample }) in &uses {
+                let unknown = if template::is_known(variable) {
+                    ""
+                } else {
+                    "\tunknown, left as is"
+                };
+                println!("{variable}\t{count}\t{}{unknown}", sample.display());
+            }
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::Validate { name, fix }),
+            ..
+        }) => {
+            let mut issues = template::validate(&path_str, &name)?;
+            if fix {
+                issues = template::fix(&path_str, &name, issues)?;
+            }
+            for issue in &issues {
+                eprintln!("Warning: {issue}");
+            }
+            if !issues.is_empty() {
+                return Err(anyhow!("Found {} issues in template {name}", issues.len()));
+            }
+            println!("Template {name} looks good");
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::Rename { from, to }),
+            ..
+        }) => {
+            template::rename(&path_str, &from, &to)?;
+            println!("Renamed template {from} to {to}");
+            let users = template::users(&projects, &from)?;
+            if !users.is_empty()
+                && confirm(&format!(
+                    "Update the {} projects created from {from}",
+                    users.len()
+                ))?
+            {
+                for (i, id) in users.iter().enumerate() {
+                    template::set_provenance(projects[id].get_path(), &to)?;
+                    println!(
+                        "Updated {}/{}: {}",
+                        i + 1,
+                        users.len(),
+                        projects[id].full_name()
+                    );
+                }
+            }
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::Delete { name }),
+            ..
+        }) => {
+            let template_path = template::template_path(&path_str, &name);
+            if !template_path.is_dir() {
+                return Err(anyhow!("Template {name} does not exist!"));
+            }
+            let users = template::users(&projects, &name)?;
+            if !users.is_empty() {
+                println!(
+                    "{} projects were created from {name}, they will keep its name as history",
+                    users.len()
+                );
+            }
+            if !confirm(&format!("Delete template {name}"))? {
+                println!("Aborting...");
+                return Ok(());
+            }
+            template::delete(&path_str, &name)?;
+            println!("Deleted template {name}");
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::Export { name, output }),
+            ..
+        }) => {
+            let output = output
+                .unwrap_or_else(|| PathBuf::from(format!("{name}.{}", export::ARCHIVE_EXTENSION)));
+            template::export(&path_str, &name, &output)?;
+            println!("Exported template {name} to {}", output.display());
+        }
+        Some(Commands::Template {
+            command: Some(TemplateCommands::Import { file, name, force }),
+            ..
+        }) => {
+            let name = template::import(&path_str, &file, name.as_deref(), force)?;
+            println!("Imported template {name} from {}", file.display());
+        }
+        Some(Commands::Template { .. }) => unreachable!(),
+        Some(Commands::AdoptGh {
+            ref user,
+            ref topic,
+            ref include,
+            ref exclude,
+            all,
+            dry_run,
+        }) => {
+            let repos = github::filter_repos(
+                github::list_repos(user)?,
+                topic.as_deref(),
+                include.as_deref(),
+                exclude.as_deref(),
+            );
+            let paths = projects.values().map(Project::get_path).collect_vec();
+            let imported = github::existing_remotes(paths.iter().map(String::as_str));
+            let (adopted, repos): (Vec<_>, Vec<_>) = repos.into_iter().partition(|repo| {
+                imported.contains(&github::normalize_remote(&repo.clone_url))
+                    || imported.contains(&github::normalize_remote(&repo.html_url))
+            });
+            for repo in adopted.iter() {
+                println!("Skipping {}: already adopted", repo.name);
+            }
+            if repos.is_empty() {
+                println!("No repositories to adopt");
+                return Ok(());
+            }
+            let selected = if all {
+                repos
+            } else {
+                let names = repos
+                    .iter()
+                    .map(|repo| match &repo.description {
+                        Some(description) => format!("{} - {}", repo.name, description),
+                        None => repo.name.clone(),
+                    })
+                    .collect_vec();
+                let Some(indices) =
+                    tui::multi_select("Repositories", &names).map_err(|err| anyhow!("{err}"))?
+                else {
+                    println!("Aborting...");
+                    return Ok(());
+                };
+                indices.into_iter().map(|i| repos[i].clone()).collect()
+            };
+            let mut id = next_id(&projects);
+            let mut failures = 0;
+            for repo in selected.iter() {
+                let name = format_name(&repo.name).map_err(|err| anyhow!(err))?;
+                let project = Project::new(&path_str, id, name, repo.created(), Local::now())
+                    .with_args(&args);
+                let path = project.get_path();
+                if dry_run {
+                    println!("Would clone {} into {}", repo.html_url, path);
+                    id += 1;
+                    continue;
+                }
+                match github::clone(repo, &path) {
+                    Ok(()) => {
+                        if let Some(description) = &repo.description {
+                            meta::save(
+                                &path,
+                                &meta::ProjectMeta {
+                                    description: description.clone(),
+                                    ..Default::default()
+                                },
+                            )?;
+                        }
+                        println!("{}", project);
+                        id += 1;
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to adopt {}: {err}", repo.name);
+                        failures += 1;
+                    }
+                }
+            }
+            if failures > 0 {
+                return Err(anyhow!("{failures} repositories could not be adopted"));
+            }
+        }
+        Some(Commands::Stats {
+            by_tag,
+            by_kind,
+            by_year,
+        }) => {
+            let grouping = if by_tag {
+                Grouping::Tag
+            } else if by_kind {
+                Grouping::Kind
+            } else if by_year {
+                Grouping::Year
+            } else {
+                Grouping::Status
+            };
+            if grouping == Grouping::Kind {
+                kind::resolve_kinds(&mut projects, &config.kind_rules)?;
+            }
+            let samples = projects
+                .values()
+                .map(|project| {
+                    let path = project.get_path();
+                    let tags = match grouping {
+                        Grouping::Tag => project
+                            .meta
+                            .as_ref()
+                            .map(|meta| meta.tags.clone())
+                            .unwrap_or_default(),
+                        _ => Vec::new(),
+                    };
+                    Ok(Sample {
+                        id: project.id,
+                        name: project.name.clone(),
+                        status: project.status.to_string(),
+                        tags,
+                        kind: project.kind.clone(),
+                        created: project.date,
+                        size: size::dir_size(&path),
+                        last_accessed: project.last_accessed,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let buckets = stats::buckets(&samples, grouping);
+            let total = stats::total(&samples);
+            let highlights = stats::highlights(&samples);
+            if json {
+                let buckets = buckets
+                    .iter()
+                    .map(
+                        |(name, aggregate)| serde_json::json!({ "name": name, "stats": aggregate }),
+                    )
+                    .collect_vec();
+                json::print(json::versioned(serde_json::json!({
+                    "buckets": buckets,
+                    "total": total,
+                    "highlights": highlights,
+                })));
+            } else {
+                let rows = buckets
+                    .iter()
+                    .map(|(name, aggregate)| (name.as_str(), aggregate))
+                    .chain([("total", &total)])
+                    .map(|(name, aggregate)| {
+                        [
+                            name.to_string(),
+                            aggregate.projects.to_string(),
+                            size::format_size(aggregate.size),
+                            aggregate.last_activity.map_or("-".to_string(), |date| {
+                                date.format("%Y-%m-%d").to_string()
+                            }),
+                        ]
+                    })
+                    .collect_vec();
+                let width =
+                    |column: usize| rows.iter().map(|row| row[column].len()).max().unwrap_or(0);
+                let (name_width, count_width, size_width) = (width(0), width(1), width(2));
+                for [name, count, size, last_activity] in rows {
+                    println!("{name:name_width$}  {count:>count_width$}  {size:>size_width$}  {last_activity}");
+                }
+                let lines = [
+                    ("oldest", &highlights.oldest, "%Y-%m-%d"),
+                    ("newest", &highlights.newest, "%Y-%m-%d"),
+                    ("accessed", &highlights.last_accessed, "%Y-%m-%d %H:%M"),
+                ];
+                if highlights.oldest.is_some() {
+                    println!();
+                }
+                for (label, highlight, format) in lines {
+                    if let Some(highlight) = highlight {
+                        println!(
+                            "{:9} {} {} ({})",
+                            format!("{label}:"),
+                            highlight.id,
+                            highlight.name,
+                            highlight.date.format(format)
+                        );
+                    }
+                }
+            }
+        }
+        Some(Commands::Tag {
+            id: Some(id),
+            tag: Some(tag),
+            ..
+        }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            let project_meta = meta::update(project.get_path(), |project_meta| {
+                if !project_meta
+                    .tags
+                    .iter()
+                    .any(|t| t.eq_ignore_ascii_case(&tag))
+                {
+                    project_meta.tags.push(tag);
+                }
+            })?;
+            println!("{}", project_meta.tags.join(", "));
+        }
+        Some(Commands::Untag { id, tag }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            let project_meta = meta::update(project.get_path(), |project_meta| {
+                project_meta.tags.retain(|t| !t.eq_ignore_ascii_case(&tag))
+            })?;
+            println!("{}", project_meta.tags.join(", "));
+        }
+        Some(Commands::Tag {
+            command: Some(TagCommands::Stats { min_count }),
+            ..
+        }) => {
+            let counts = meta::tag_counts(projects.values().filter_map(|p| p.meta.as_ref()))
+                .into_iter()
+                .filter(|(_, count)| *count >= min_count)
+                .sorted_by(|(tag1, count1), (tag2, count2)| count2.cmp(count1).then(tag1.cmp(tag2)))
+                .collect_vec();
+            if json {
+                let counts = counts
+                    .iter()
+                    .map(|(tag, count)| serde_json::json!({ "tag": tag, "count": count }))
+                    .collect_vec();
+                json::print(json::versioned(serde_json::json!({ "tags": counts })));
+            } else {
+                let width = counts.iter().map(|(tag, _)| tag.len()).max().unwrap_or(0);
+                for (tag, count) in counts {
+                    println!("{tag:width$}  {count}");
+                }
+            }
+        }
+        Some(Commands::Doctor {
+            fix,
+            projects: health_summary,
+        }) => {
+            let scanned = projects
+                .values()
+                .map(|project| Ok((project, meta::load(project.get_path())?)))
+                .collect::<Result<Vec<_>>>()?;
+            let mismatches =
+                doctor::date_mismatches(scanned.iter().map(|(p, meta)| (*p, meta.as_ref())));
+            for mismatch in mismatches.iter() {
+                println!(
+                    "{}: folder date {} disagrees with metadata date {}",
+                    projects[&mismatch.id].full_name(),
+                    mismatch.folder_date,
+                    mismatch.meta_date
+                );
+            }
+            let scan = home::scan();
+            for skipped in scan.skipped.iter() {
+                let file_name = skipped.file_name().unwrap_or_default().to_string_lossy();
+                let reason = home::parse_project_dir_name(&file_name)
+                    .err()
+                    .unwrap_or("it isn't valid UTF-8");
+                println!(
+                    "{}: not named like {} because {reason}, it is not shown",
+                    skipped.display(),
+                    home::NAMING_SCHEME
+                );
+            }
+            if !scan.skipped.is_empty() {
+                println!(
+                    "Skipped {} of {} folders in the status folders",
+                    scan.skipped.len(),
+                    scan.parsed + scan.skipped.len()
+                );
+            }
+            let strays = template::stray_entries(&path_str)?;
+            for stray in strays.iter() {
+                println!("templates/{stray}: not a folder, it is not offered as a template");
+            }
+            for (id, path) in scan.duplicates.iter() {
+                println!(
+                    "{}: id {id} is also used by {}, it is not shown",
+                    path.display(),
+                    projects[id].get_path()
+                );
+            }
+            let missing = doctor::missing_status_dirs(&path_str);
+            for status in missing.iter() {
+                println!("{status}/: missing, projects can't be moved to {status}");
+            }
+            if template::templates_root(&path_str).is_dir()
+                && template::names(&path_str)?.is_empty()
+            {
+                println!(
+                    "Note: templates/ has no templates, so `vpm new --template` has none to offer"
+                );
+            }
+            let found = mismatches.len()
+                + scan.skipped.len()
+                + strays.len()
+                + scan.duplicates.len()
+                + missing.len();
+            let mut unfixed = found;
+            if fix.is_some() {
+                for status in missing.iter() {
+                    fs::create_dir_all(Path::new(&path_str).join(status.to_string()))?;
+                    println!("Created {status}/");
+                    unfixed -= 1;
+                }
+                let mut next_id = projects
+                    .keys()
+                    .chain(scan.duplicates.iter().map(|(id, _)| id))
+                    .max()
+                    .map_or(1, |id| id + 1);
+                for (_, path) in scan.duplicates.iter() {
+                    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let Ok(dir_name) = home::parse_project_dir_name(&file_name) else {
+                        continue;
+                    };
+                    let renamed = Project::new(
+                        &path_str,
+                        next_id,
+                        dir_name.name,
+                        dir_name.date,
+                        Local::now(),
+                    )
+                    .full_name();
+                    let target = path.with_file_name(&renamed);
+                    if !confirm(&format!("Renumber {} to {renamed}", path.display()))? {
+                        continue;
+                    }
+                    if fs::symlink_metadata(&target).is_ok() {
+                        return Err(anyhow!("{} already exists!", target.display()));
+                    }
+                    fsops::move_dir(path, &target)?;
+                    println!("Renumbered project: {renamed}");
+                    next_id += 1;
+                    unfixed -= 1;
+                }
+            }
+            if let Some(Some(fix)) = fix {
+                unfixed -= mismatches.len();
+                for action in doctor::plan_date_fixes(&mismatches, fix) {
+                    match action {
+                        FixAction::RenameFolder { id, date } => {
+                            let project = &projects[&id];
+                            let new_project = Project::new(
+                                &project.root,
+                                id,
+                                &project.name,
+                                date,
+                                project.last_accessed,
+                            )
+                            .with_status(project.status)
+                            .with_id_width(project.id_width);
+                            rename_project(project, &new_project)?;
+                            println!("Renamed project: {}", new_project.full_name());
+                        }
+                        FixAction::UpdateMeta { id, date } => {
+                            let path = projects[&id].get_path();
+                            let mut project_meta = meta::load(&path)?.unwrap_or_default();
+                            project_meta.created = Some(date);
+                            meta::save(&path, &project_meta)?;
+                            println!("Updated metadata: {}", projects[&id].full_name());
+                        }
+                    }
+                }
+            }
+            if found == 0 && !health_summary {
+                println!("No problems found");
+            }
+            if health_summary {
+                health::resolve_health(&mut projects, &config.health_checks)?;
+                for (name, missing) in health::missing_counts(projects.values()) {
+                    println!("{missing}/{} projects lack {name}", projects.len());
+                }
+            }
+            if unfixed > 0 {
+                let problems = if unfixed == 1 { "problem" } else { "problems" };
+                match fix {
+                    Some(_) => println!("{unfixed} of {found} {problems} left unfixed"),
+                    None => println!("Found {unfixed} {problems}, `vpm doctor --fix` fixes some"),
+                }
+                process::exit(1);
+            }
+        }
+        Some(Commands::Status {
+            id,
+            status: Some(status),
+        }) => {
+            let project = projects
+                .get_mut(&id)
+                .ok_or(VpmError::ProjectNotFound { id })?;
+            if project.status == status {
+                return Err(anyhow!("{} is already {status}", project.full_name()));
+            }
+            project.change_status(status, None)?;
+            if json {
+                json::print(json::versioned(
+                    serde_json::json!({ "id": project.id, "status": project.status }),
+                ));
+            } else {
+                println!("Moved {} to {}", project.full_name(), project.get_path());
+            }
+        }
+        Some(Commands::Status { id, status: None }) => {
+            let project = projects.get(&id).ok_or(VpmError::ProjectNotFound { id })?;
+            if json {
+                json::print(json::versioned(
+                    serde_json::json!({ "id": project.id, "status": project.status }),
+                ));
+            } else {
+                println!("{}", project.status);
+            }
+        }
+        Some(Commands::StatusHistory { id }) => {
+            let tombstone = tombstone::find(&paths, id)?;
+            let Some(project) = projects.get(&id) else {
+                let tombstone = tombstone.ok_or(VpmError::ProjectNotFound { id })?;
+                print_tombstone(&tombstone);
+                return Ok(());
+            };
+            let history = meta::load(project.get_path())?
+                .map(|meta| meta.history)
+                .unwrap_or_default();
+            for change in history {
+                match change.message {
+                    Some(message) => println!("{} {}: {:?}", change.date, change.status, message),
+                    None => println!("{} {}", change.date, change.status),
+                }
+            }
+            if let Some(tombstone) = tombstone {
+                print_tombstone(&tombstone);
+            }
+        }
+        Some(Commands::Archive {
+            ids,
+            message,
+            to: Some(to),
+            keep_local,
+            delete_local,
+            ..
+        }) => {
+            let local = if keep_local {
+                LocalAction::Keep
+            } else if delete_local {
+                LocalAction::Delete
+            } else {
+                LocalAction::Trash
+            };
+            let mut failed = Vec::new();
+            for id in ids {
+                let Some(project) = projects.get(&id) else {
+                    eprintln!("Warning: {}", VpmError::ProjectNotFound { id });
+                    failed.push(id);
+                    continue;
+                };
+                match export::archive_to(project, &to, local, message.clone(), &paths) {
+                    Ok(destination) => println!(
+                        "Archived {} to {}",
+                        project.full_name(),
+                        destination.display()
+                    ),
+                    Err(err) => {
+                        eprintln!("Warning: {err}");
+                        failed.push(id);
+                    }
+                }
+            }
+            if !failed.is_empty() {
+                return Err(anyhow!("Failed to archive {}", failed.iter().join(", ")));
+            }
+        }
+        Some(Commands::Archive { ids, message, .. }) => {
+            move_projects(&mut projects, &ids, Status::Archived, message)?
+        }
+        Some(Commands::Pause { ids, .. }) => {
+            move_projects(&mut projects, &ids, Status::Paused, None)?
+        }
+        Some(Commands::Resume { ids, .. }) => {
+            move_projects(&mut projects, &ids, Status::Active, None)?
+        }
+        Some(Commands::Rename { .. }) => unreachable!("clap requires an id and name"),
+        Some(Commands::Tag { .. }) => unreachable!("clap requires an id and tag, or a subcommand"),
+        Some(Commands::Config { .. })
+        | Some(Commands::Completions { .. })
+        | Some(Commands::ProjectCount { .. }) => {
+            unreachable!("config, completions and project-count run before loading projects")
         }
         None => {
-            tui::start(projects).unwrap();
+            kind::resolve_kinds(&mut projects, &config.kind_rules)?;
+            tui::start(projects, config.tui_sort()?).map_err(|err| anyhow!("{err}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn run_config(config: &Config, paths: &Paths, command: &ConfigCommands) -> Result<()> {
+    match command {
+        ConfigCommands::Paths => {
+            println!("config: {}", paths.config_file().display());
+            println!("cache:  {}", paths.cache_dir.display());
+            println!("index:  {}", paths.index_file().display());
+            println!("state:  {}", paths.state_dir.display());
+        }
+        ConfigCommands::Show => {
+            println!("# {}", config.path.display());
+            print!("{}", config.show()?);
+        }
+        ConfigCommands::Get { key } => {
+            if let Some(value) = &config.get(key)?.value {
+                println!("{value}");
+            }
         }
+        ConfigCommands::Set { key, value } => config::set(&config.path, key, value)?,
+        ConfigCommands::Init => wizard::init_config(&config.path)?,
     }
     Ok(())
 }
 
+/// Move the project `id` to `status`, printing the result.
+fn move_project(
+    projects: &mut BTreeMap<usize, Project>,
+    id: usize,
+    status: Status,
+    message: Option<String>,
+) -> Result<()> {
+    let project = projects
+        .get_mut(&id)
+        .ok_or(VpmError::ProjectNotFound { id })?;
+    project.change_status(status, message)?;
+    println!("{}", project.get_path());
+    Ok(())
+}
+
+/// Move each of `ids` to `status`, warning about the ones that fail instead of stopping, then
+/// fail with the ids that weren't moved.
+fn move_projects(
+    projects: &mut BTreeMap<usize, Project>,
+    ids: &[usize],
+    status: Status,
+    message: Option<String>,
+) -> Result<()> {
+    let mut failed = Vec::new();
+    for &id in ids {
+        if let Err(err) = move_project(projects, id, status, message.clone()) {
+            eprintln!("Warning: {err}");
+            failed.push(id);
+        }
+    }
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "Failed to move {} to {status}",
+            failed.iter().join(", ")
+        )),
+    }
+}
+
+fn print_tombstone(tombstone: &Tombstone) {
+    print!(
+        "{} exported to {} (sha256 {})",
+        tombstone.date,
+        tombstone.destination.display(),
+        tombstone.sha256
+    );
+    match &tombstone.message {
+        Some(message) => println!(": {:?}", message),
+        None => println!(),
+    }
+}
+
+/// The ID after the highest one in use, 1 for the first project.
+fn next_id(projects: &BTreeMap<usize, Project>) -> usize {
+    projects.last_key_value().map(|kv| kv.0 + 1).unwrap_or(1)
+}
+
+/// The width list rows are truncated to: the terminal's, if stdout is one and `truncate` is
+/// set. Rows of paths must stay whole, so they are never truncated.
+fn row_width(truncate: bool) -> Option<usize> {
+    match crossterm::terminal::size() {
+        Ok((columns, _)) if io::stdout().is_terminal() && truncate => Some(columns as usize),
+        _ => None,
+    }
+}
+
+/// Ask a yes/no question on stdin, defaulting to no.
+pub fn confirm(question: &str) -> io::Result<bool> {
+    print!("{question} [y/N]? ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase() == "y")
+}
+
+/// Move `from` to the location of `to`, in the same status folder, refusing to overwrite
+/// anything already there.
+fn rename_project(from: &Project, to: &Project) -> Result<()> {
+    let new_path = to.get_path();
+    if fs::symlink_metadata(&new_path).is_ok() {
+        return Err(anyhow!("A project already exists at {new_path}!"));
+    }
+    fsops::move_dir(Path::new(&from.get_path()), Path::new(&new_path))
+}
+
 fn format_name(name: &str) -> Result<String, String> {
     let name = name.trim();
     if name.is_empty() {
         return Err("Name must not be empty!".to_string());
     }
-    Ok(name
+    if name.contains(['/', '\\']) {
+        return Err("Name must not contain path separators!".to_string());
+    }
+    let formatted = name
         .replace(|c: char| !c.is_ascii(), "")
         .to_ascii_lowercase()
         .split_whitespace()
         .collect::<Vec<_>>()
-        .join("-"))
+        .join("-");
+    if formatted.is_empty() {
+        return Err("Name has no ASCII characters left to use!".to_string());
+    }
+    if formatted.chars().all(|c| c == '-' || c == '.') {
+        return Err("Name must not be only dashes or dots!".to_string());
+    }
+    Ok(formatted)
 }
 
-fn read_files(path: impl Into<String>, args: &Args) -> BTreeMap<usize, Project> {
-    let path_name = path.into();
-    fs::read_dir(&path_name)
-        .expect(format!("failed to read directory: {}", &path_name).as_str())
-        .filter_map(|res| {
-            res.ok()
-                .and_then(|dir| dir.file_name().into_string().ok().map(|s| (dir.path(), s)))
-                .and_then(|(path, status_dir)| {
-                    Status::try_from(status_dir)
-                        .ok()
-                        .map(|status| (path, status))
+/// The projects whose folder doesn't pad the id to `width` hex digits, with the same project
+/// padded to `width`.
+fn migration_plan(projects: &BTreeMap<usize, Project>, width: usize) -> Vec<(&Project, Project)> {
+    projects
+        .values()
+        .filter(|project| project.id_width != width)
+        .map(|project| {
+            let mut padded = project.clone();
+            padded.id_width = width;
+            (project, padded)
+        })
+        .collect()
+}
+
+/// Let the user pick among the projects, leaving out those that already have `status`.
+fn pick_ids(projects: &BTreeMap<usize, Project>, status: Option<Status>) -> Result<Vec<usize>> {
+    let candidates = projects
+        .values()
+        .filter(|project| Some(project.status) != status)
+        .collect_vec();
+    let items = candidates
+        .iter()
+        .map(|project| format!("{} ({})", project.full_name(), project.status))
+        .collect_vec();
+    let picked = pick::select_many("Projects", &items)?;
+    Ok(picked
+        .into_iter()
+        .map(|index| candidates[index].id)
+        .collect())
+}
+
+/// Parse a project ID written in decimal, or in hex with a `0x` or `p` prefix like in folder
+/// names.
+fn parse_id(s: &str) -> Result<usize> {
+    if let Ok(id) = s.parse() {
+        return Ok(id);
+    }
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix('p'))
+        .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| anyhow!("`{s}` is not a decimal ID or a hex ID like 0x1A or p1A"))
+}
+
+/// The ID of the project `selector` refers to: a known ID as `parse_id` reads it, else the exact name of a
+/// project, else with `fuzzy` the projects whose name fuzzy matches it. Exactly one project must match.
+fn resolve_id(selector: &str, projects: &BTreeMap<usize, Project>, fuzzy: bool) -> Result<usize> {
+    let parsed = parse_id(selector).ok();
+    if let Some(id) = parsed.filter(|id| projects.contains_key(id)) {
+        return Ok(id);
+    }
+    let mut matches = projects
+        .values()
+        .filter(|project| project.name == selector)
+        .collect_vec();
+    // Names like `pdf` also read as hex IDs, so an unknown ID only fails once no name matches.
+    if let (Some(id), true) = (parsed, matches.is_empty()) {
+        return Err(VpmError::ProjectNotFound { id }.into());
+    }
+    if matches.is_empty() && fuzzy {
+        let matcher = SkimMatcherV2::default();
+        matches = projects
+            .values()
+            .filter(|project| matcher.fuzzy_match(&project.name, selector).is_some())
+            .collect_vec();
+    }
+    match matches.as_slice() {
+        [project] => Ok(project.id),
+        [] => Err(anyhow!("No project is named `{selector}`!")),
+        _ => Err(anyhow!(
+            "Several projects match `{selector}`: {}",
+            matches.iter().map(|project| project.full_name()).join(", ")
+        )),
+    }
+}
+
+/// Lowest fuzzy score `resolve_best_id` accepts, so a stray letter in common doesn't count as a match.
+const MIN_BEST_SCORE: i64 = 40;
+
+/// The ID of the project `selector` refers to: a known ID as `parse_id` reads it, else the exact name of a
+/// project, else the project whose name fuzzy matches it best, like `vpm search` ranks them. Ties and weak matches
+/// are refused with the candidates listed.
+fn resolve_best_id(selector: &str, projects: &BTreeMap<usize, Project>) -> Result<usize> {
+    if let Ok(id) = resolve_id(selector, projects, false) {
+        return Ok(id);
+    }
+    // Neither a known ID nor a name, so an ID was meant.
+    if let Ok(id) = parse_id(selector) {
+        return Err(VpmError::ProjectNotFound { id }.into());
+    }
+    let matcher = SkimMatcherV2::default();
+    let candidates = projects
+        .values()
+        .filter_map(|project| {
+            let score = matcher.fuzzy_match(&project.name, selector)?;
+            Some((project, score))
+        })
+        .sorted_by(|(_, score1), (_, score2)| score2.cmp(score1))
+        .collect_vec();
+    let list = |candidates: &[(&Project, i64)]| {
+        candidates
+            .iter()
+            .map(|(project, _)| format!("\n  {}", project.full_name()))
+            .join("")
+    };
+    match candidates.as_slice() {
+        [] => Err(anyhow!("No project matches `{selector}`!")),
+        [(project, best), rest @ ..] if *best >= MIN_BEST_SCORE => {
+            let tied = rest.iter().take_while(|(_, score)| score == best).count();
+            match tied {
+                0 => Ok(project.id),
+                _ => Err(anyhow!(
+                    "Several projects match `{selector}` equally well, pass an ID:{}",
+                    list(&candidates[..=tied])
+                )),
+            }
+        }
+        _ => Err(anyhow!(
+            "No project matches `{selector}` well enough, pass an ID. Closest:{}",
+            list(&candidates[..candidates.len().min(5)])
+        )),
+    }
+}
+
+/// Point out that `project` needs more hex digits than the other project folders, which then no
+/// longer sort by id.
+fn hint_id_width(project: &Project, quiet: bool) {
+    let width = home::hex_width(project.id);
+    if !quiet && width > home::id_width() {
+        eprintln!(
+            "Hint: {} needs {width} hex digits, run `vpm migrate` to pad the other project folders \
+             so they keep sorting by id",
+            project.full_name()
+        );
+    }
+}
+
+/// Point out trashed projects older than `trash.retention`, or delete them with
+/// `trash.auto_empty`. The notice only goes to a terminal, never to piped or JSON output.
+fn check_trash(config: &Config, root: &str, json: bool) -> Result<()> {
+    let expired = trash::expired(trash::list(root)?, config.trash_retention()?, when::today());
+    if expired.is_empty() {
+        return Ok(());
+    }
+    let auto_empty = config.trash_auto_empty()?;
+    if auto_empty {
+        trash::empty(&expired)?;
+    }
+    if json || !io::stdout().is_terminal() || !io::stderr().is_terminal() {
+        return Ok(());
+    }
+    let retention = config.trash_retention.value.as_deref().unwrap_or_default();
+    if auto_empty {
+        eprintln!(
+            "Deleted {} trashed projects older than {retention}",
+            expired.len()
+        );
+    } else {
+        eprintln!(
+            "{} trashed projects older than {retention}, run `vpm trash empty` to delete them",
+            expired.len()
+        );
+    }
+    Ok(())
+}
+
+/// Ask what `vpm new` should do about the existing `path`.
+fn ask_collision(path: &str) -> Result<create::Resolution> {
+    println!("{path} already exists.");
+    loop {
+        print!("[r]ename, [s]uffix the name, [a]dopt the existing folder or [q]uit? ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(create::Resolution::Abort);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "r" | "rename" => {
+                print!("New name: ");
+                io::stdout().flush()?;
+                let mut name = String::new();
+                io::stdin().read_line(&mut name)?;
+                return Ok(create::Resolution::Rename(name.trim().to_string()));
+            }
+            "s" | "suffix" => return Ok(create::Resolution::Suffix),
+            "a" | "adopt" => return Ok(create::Resolution::Adopt),
+            "q" | "quit" | "" => return Ok(create::Resolution::Abort),
+            _ => {}
+        }
+    }
+}
+
+/// Count the project folders with `status` without reading their metadata.
+fn count_projects(root: &str, status: Status) -> usize {
+    let mode = home::mode();
+    if mode == LayoutMode::Flat && status != Status::Active {
+        return 0;
+    }
+    fs::read_dir(mode.status_dir(root, status))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    match mode {
+                        LayoutMode::Nested => name.starts_with('p'),
+                        LayoutMode::Flat => home::is_project_dir_name(&name),
+                    }
                 })
-                .map(|(path, status)| {
-                    fs::read_dir(path)
-                        .unwrap()
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Scan the project home at `path`. Status folders may be symlinks, but one that resolves to the
+/// project home, one of its parents or an already scanned folder is skipped.
+fn read_files(
+    path: impl Into<String>,
+    canonical_root: &Path,
+    args: &Args,
+) -> BTreeMap<usize, Project> {
+    let path_name = path.into();
+    let mut scanned = HashSet::new();
+    let entries = fs::read_dir(&path_name)
+        .unwrap_or_else(|_| panic!("failed to read directory: {}", &path_name))
+        .filter_map(|res| res.ok());
+    match home::mode() {
+        LayoutMode::Nested => entries
+            // Real folders first, so that they win over symlinks pointing at them, then active before
+            // paused before archived so the same project wins every time ids collide.
+            .sorted_by_key(|dir| {
+                (
+                    dir.file_type()
+                        .is_ok_and(|file_type| file_type.is_symlink()),
+                    Reverse(Status::try_from(dir.file_name().to_string_lossy().into_owned()).ok()),
+                )
+            })
+            .filter_map(|dir| {
+                let status = Status::try_from(dir.file_name().into_string().ok()?).ok()?;
+                let target = fs::canonicalize(dir.path()).ok()?;
+                if canonical_root.starts_with(&target) {
+                    eprintln!(
+                        "Warning: skipping {}, it points to the project home or one of its parents",
+                        dir.path().display()
+                    );
+                    return None;
+                }
+                if !scanned.insert(target.clone()) {
+                    eprintln!(
+                        "Warning: skipping {}, it points to {} which is already scanned",
+                        dir.path().display(),
+                        target.display()
+                    );
+                    return None;
+                }
+                let projects = match fs::read_dir(dir.path()) {
+                    Ok(projects) => projects,
+                    Err(err) => {
+                        eprintln!("Warning: skipping {}, {err}", dir.path().display());
+                        return None;
+                    }
+                };
+                Some(
+                    projects
                         .filter_map(|project| {
-                            if !project
-                                .as_ref()
-                                .unwrap()
-                                .file_name()
-                                .to_str()
-                                .unwrap()
-                                .starts_with('p')
+                            let project = project.ok()?;
+                            let read = read_project(&path_name, &project, status, args);
+                            if read.is_some() {
+                                home::record_parsed();
+                            } else if !project.file_name().to_string_lossy().starts_with('.')
+                                && project.path().is_dir()
                             {
-                                return None;
+                                home::record_skipped(project.path());
                             }
-                            let project = project.unwrap();
-                            let project_vec: Vec<String> = project
-                                .file_name()
-                                .to_str()
-                                .unwrap()
-                                .to_string()
-                                .split('-')
-                                .map(|s| s.to_string())
-                                .collect();
-                            let id = usize::from_str_radix(&project_vec[0][1..], 16).unwrap();
-                            let name = project_vec[1..project_vec.len() - 3].join("-");
-                            let date = NaiveDate::parse_from_str(
-                                project_vec[project_vec.len() - 3..=project_vec.len() - 1]
-                                    .join("-")
-                                    .as_str(),
-                                "%Y-%m-%d",
-                            )
-                            .expect("Could not parse date");
-                            let modified: DateTime<Local> = project
-                                .metadata()
-                                .unwrap()
-                                .accessed()
-                                .map(|time| time.into())
-                                .unwrap_or(
-                                    date.and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-                                        .and_local_timezone(Local)
-                                        .unwrap(),
-                                );
-                            Some((
-                                id,
-                                Project::new(id, name, date, modified)
-                                    .with_args(args)
-                                    .with_status(status),
-                            ))
+                            read
                         })
-                        .collect_vec()
-                })
-        })
-        .concat()
-        .into_iter()
-        .collect()
+                        .collect_vec(),
+                )
+            })
+            .concat()
+            .into_iter()
+            .fold(BTreeMap::new(), keep_first),
+        LayoutMode::Flat => entries
+            .filter_map(|project| read_project(&path_name, &project, Status::Active, args))
+            .fold(BTreeMap::new(), keep_first),
+    }
 }
 
-fn init_shell(shell: InitShells) -> Result<()> {
+/// Add `project` unless its id is taken, in which case it's recorded as a duplicate for doctor.
+fn keep_first(
+    mut projects: BTreeMap<usize, Project>,
+    (id, project): (usize, Project),
+) -> BTreeMap<usize, Project> {
+    match projects.get(&id) {
+        Some(_) => home::record_duplicate(id, PathBuf::from(project.get_path())),
+        None => {
+            projects.insert(id, project);
+        }
+    }
+    projects
+}
+
+/// Read the project in the folder `project`, or `None` when it isn't named like a project. Folders
+/// starting with `p` that almost are get a warning, unless `--quiet`.
+fn read_project(
+    root: &str,
+    project: &fs::DirEntry,
+    status: Status,
+    args: &Args,
+) -> Option<(usize, Project)> {
+    let file_name = project.file_name();
+    let parsed = file_name
+        .to_str()
+        .and_then(|name| home::parse_project_dir_name(name).ok());
+    let Some(home::DirName {
+        id,
+        id_width,
+        name,
+        date,
+    }) = parsed
+    else {
+        if file_name.to_string_lossy().starts_with('p') && !args.quiet && project.path().is_dir() {
+            eprintln!(
+                "Warning: skipping {}, it isn't named like {}",
+                project.path().display(),
+                home::NAMING_SCHEME
+            );
+        }
+        return None;
+    };
+    // Files named like projects aren't projects, but symlinks to folders are.
+    if !project.path().is_dir() {
+        return None;
+    }
+    let metadata = project.metadata().ok();
+    let (accessed, source) = accessed::resolve(
+        metadata.as_ref().and_then(|m| m.accessed().ok()),
+        metadata.as_ref().and_then(|m| m.modified().ok()),
+        date,
+    );
+    let mut project = Project::new(root, id, name, date, accessed)
+        .with_args(args)
+        .with_status(status);
+    project.id_width = id_width;
+    project.accessed_source = source;
+    project.meta = match meta::load(project.get_path()) {
+        Ok(project_meta) => project_meta,
+        Err(err) => {
+            if !args.quiet {
+                eprintln!(
+                    "Warning: ignoring the metadata of {}: {err}",
+                    project.full_name()
+                );
+            }
+            None
+        }
+    };
+    project.description = project
+        .meta
+        .as_ref()
+        .map(|project_meta| project_meta.description.clone())
+        .filter(|description| !description.is_empty());
+    Some((id, project))
+}
+
+fn init_shell(shell: Option<InitShells>) -> Result<()> {
+    let shell = match shell {
+        Some(shell) => shell,
+        None => {
+            let detected = shells::detect_shell(
+                env::var("SHELL").ok().as_deref(),
+                shells::parent_process().as_deref(),
+            );
+            let shell = match detected.as_deref() {
+                Some("fish") => InitShells::Fish,
+                Some("zsh") => InitShells::Zsh,
+                Some("bash") => InitShells::Bash,
+                Some("nu") => InitShells::Nushell,
+                _ => {
+                    return Err(anyhow!(
+                        "Could not detect a supported shell (found {}), run `vpm init <SHELL>` with one of: {}",
+                        detected.as_deref().unwrap_or("nothing"),
+                        shells::SUPPORTED_SHELLS.join(", ")
+                    ))
+                }
+            };
+            println!("Detected shell: {}", detected.unwrap_or_default());
+            shell
+        }
+    };
     match shell {
         InitShells::Fish => shells::init_fish(),
         InitShells::Zsh => shells::init_zsh(),
-        #[allow(unreachable_patterns)]
-        sh => unimplemented!("init_shell({sh:?})"),
+        InitShells::Bash => shells::init_bash(),
+        InitShells::Nushell => shells::init_nushell(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_id_reads_decimal_and_hex() {
+        for (input, id) in [("0x1A", 26), ("26", 26), ("p1A", 26), ("p01", 1), ("0", 0)] {
+            assert_eq!(parse_id(input).unwrap(), id, "{input}");
+        }
+    }
+
+    fn project(id: usize, id_width: usize) -> Project {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let mut project =
+            Project::new("/projects", id, format!("project-{id}"), date, Local::now());
+        project.id_width = id_width;
+        project
+    }
+
+    #[test]
+    fn folder_names_sort_by_id_past_ff() {
+        let ids = [0x0F, 0xFE, 0xFF, 0x100, 0x101];
+        let names = ids
+            .iter()
+            .map(|&id| project(id, home::hex_width(0x101)).full_name())
+            .collect_vec();
+        assert_eq!(names, names.iter().cloned().sorted().collect_vec());
+        assert_eq!(names[2], "p0FF-project-255-2024-01-15");
+        assert_eq!(names[3], "p100-project-256-2024-01-15");
+    }
+
+    #[test]
+    fn folder_names_round_trip_past_ff() {
+        for (id, width) in [(0xFF, 2), (0xFF, 3), (0x100, 3), (0x101, 3), (0x101, 4)] {
+            let project = project(id, width);
+            let parsed = home::parse_project_dir_name(&project.full_name()).unwrap();
+            assert_eq!(
+                (parsed.id, parsed.id_width, parsed.name, parsed.date),
+                (id, width, project.name.clone(), project.date),
+                "{}",
+                project.full_name()
+            );
+        }
+    }
+
+    #[test]
+    fn migration_plan_pads_narrow_folders() {
+        let projects = [project(0x01, 2), project(0xFF, 2), project(0x100, 3)]
+            .into_iter()
+            .map(|project| (project.id, project))
+            .collect::<BTreeMap<_, _>>();
+        let renames = migration_plan(&projects, 3)
+            .into_iter()
+            .map(|(from, to)| (from.full_name(), to.full_name()))
+            .collect_vec();
+        assert_eq!(
+            renames,
+            [
+                (
+                    "p01-project-1-2024-01-15".to_string(),
+                    "p001-project-1-2024-01-15".to_string()
+                ),
+                (
+                    "pFF-project-255-2024-01-15".to_string(),
+                    "p0FF-project-255-2024-01-15".to_string()
+                ),
+            ]
+        );
+        assert_eq!(migration_plan(&projects, 4).len(), 3);
+    }
+
+    fn named(names: &[&str]) -> BTreeMap<usize, Project> {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                (
+                    i + 1,
+                    Project::new("/projects", i + 1, *name, date, Local::now()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hex_looking_names_resolve_when_no_such_id_exists() {
+        let projects = named(&["pdf", "p02", "notes"]);
+        assert_eq!(resolve_id("pdf", &projects, false).unwrap(), 1);
+        assert_eq!(resolve_id("pdf", &projects, true).unwrap(), 1);
+        // A known ID wins over a project named like it.
+        assert_eq!(resolve_id("p02", &projects, false).unwrap(), 2);
+        assert_eq!(resolve_id("3", &projects, false).unwrap(), 3);
+        let err = resolve_id("pde", &projects, true).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(VpmError::ProjectNotFound { id: 0xDE })
+        ));
+    }
+
+    #[test]
+    fn best_match_tries_names_before_giving_up_on_an_id() {
+        let projects = named(&["pdf", "pdf-tools", "notes"]);
+        assert_eq!(resolve_best_id("pdf", &projects).unwrap(), 1);
+        assert_eq!(resolve_best_id("p02", &projects).unwrap(), 2);
+        assert_eq!(resolve_best_id("notes", &projects).unwrap(), 3);
+        let err = resolve_best_id("pde", &projects).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(VpmError::ProjectNotFound { id: 0xDE })
+        ));
+    }
+
+    #[test]
+    fn parse_id_rejects_garbage() {
+        for input in ["", "0x", "p", "pZZ", "1A", "-1", "foo"] {
+            assert!(parse_id(input).is_err(), "{input}");
+        }
     }
 }
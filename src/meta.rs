@@ -0,0 +1,126 @@
+use chrono::{Local, NaiveDate};
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{atomic, paths::Paths, Status};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+const META_DIR: &str = ".vpm";
+const META_FILENAME: &str = "meta.toml";
+const NOTES_FILENAME: &str = "notes.md";
+
+/// Metadata stored alongside a project in `.vpm/meta.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<NaiveDate>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Overrides the kind inferred from the project's contents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// From 1 (highest) to 5 (lowest).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// The template the project was created from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<StatusChange>,
+}
+
+/// An entry in a project's status history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub date: NaiveDate,
+    pub status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+pub fn meta_path(project_path: impl AsRef<Path>) -> PathBuf {
+    project_path.as_ref().join(META_DIR).join(META_FILENAME)
+}
+
+pub fn notes_path(project_path: impl AsRef<Path>) -> PathBuf {
+    project_path.as_ref().join(META_DIR).join(NOTES_FILENAME)
+}
+
+/// Load the metadata of the project at `project_path`, or `None` if it has no sidecar, or isn't
+/// a folder at all.
+pub fn load(project_path: impl AsRef<Path>) -> Result<Option<ProjectMeta>> {
+    let path = meta_path(project_path);
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(Some(toml::from_str(&contents)?)),
+        Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) => {
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save(project_path: impl AsRef<Path>, meta: &ProjectMeta) -> Result<()> {
+    atomic::write(meta_path(project_path), toml::to_string(meta)?)?;
+    Ok(())
+}
+
+/// Count how many projects use each tag.
+pub fn tag_counts<'a>(metas: impl IntoIterator<Item = &'a ProjectMeta>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for tag in metas.into_iter().flat_map(|meta| meta.tags.iter()) {
+        *counts.entry(tag.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Append a status change to the history of the project at `project_path`.
+pub fn record_status(
+    project_path: impl AsRef<Path>,
+    status: Status,
+    message: Option<String>,
+) -> Result<()> {
+    let _lock = atomic::lock()?;
+    let mut meta = load(&project_path)?.unwrap_or_default();
+    meta.history.push(StatusChange {
+        date: Local::now().date_naive(),
+        status,
+        message,
+    });
+    save(project_path, &meta)
+}
+
+/// Replace the description of the project at `project_path`.
+pub fn set_description(project_path: impl AsRef<Path>, description: &str) -> Result<()> {
+    update(project_path, |meta| {
+        meta.description = description.to_string()
+    })
+    .map(|_| ())
+}
+
+/// Change the metadata of the project at `project_path` in place, creating it if needed.
+pub fn update(
+    project_path: impl AsRef<Path>,
+    modify: impl FnOnce(&mut ProjectMeta),
+) -> Result<ProjectMeta> {
+    update_at(&Paths::resolve()?.lock_file(), project_path, modify)
+}
+
+/// [`update`], holding the lock at `lock` instead of vpm's.
+pub fn update_at(
+    lock: &Path,
+    project_path: impl AsRef<Path>,
+    modify: impl FnOnce(&mut ProjectMeta),
+) -> Result<ProjectMeta> {
+    let _lock = atomic::lock_at(lock)?;
+    let mut meta = load(&project_path)?.unwrap_or_default();
+    modify(&mut meta);
+    save(project_path, &meta)?;
+    Ok(meta)
+}
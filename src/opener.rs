@@ -0,0 +1,30 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// The program opening folders in the default file manager: `open` on macOS, `explorer` on
+/// Windows and `xdg-open` elsewhere.
+const FILE_MANAGER: &str = if cfg!(target_os = "macos") {
+    "open"
+} else if cfg!(target_os = "windows") {
+    "explorer"
+} else {
+    "xdg-open"
+};
+
+/// Show the folder `path` in the default file manager, without waiting for it.
+pub fn open_folder(path: &Path) -> Result<()> {
+    if !path.is_dir() {
+        return Err(anyhow!("{} does not exist!", path.display()));
+    }
+    Command::new(FILE_MANAGER)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| anyhow!("Failed to run {FILE_MANAGER}: {err}"))?;
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use color_eyre::eyre::{anyhow, Result};
+use directories::ProjectDirs;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILENAME: &str = "config.toml";
+const INDEX_FILENAME: &str = "index.toml";
+const TOMBSTONES_FILENAME: &str = "tombstones.toml";
+const LOCK_FILENAME: &str = "vpm.lock";
+
+/// Where vpm keeps its own files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    pub state_dir: PathBuf,
+}
+
+impl Paths {
+    /// Resolve the platform directories, or put everything under `$VPM_DATA_DIR` when it is set.
+    pub fn resolve() -> Result<Self> {
+        match data_dir_override() {
+            Some(dir) => Ok(Self {
+                config_dir: dir.clone(),
+                cache_dir: dir.join("cache"),
+                state_dir: dir.join("state"),
+            }),
+            None => Self::platform(),
+        }
+    }
+
+    fn platform() -> Result<Self> {
+        let dirs = ProjectDirs::from("", "", "vpm")
+            .ok_or(anyhow!("Could not determine the home directory!"))?;
+        Ok(Self {
+            config_dir: dirs.config_dir().to_path_buf(),
+            cache_dir: dirs.cache_dir().to_path_buf(),
+            state_dir: dirs
+                .state_dir()
+                .unwrap_or_else(|| dirs.data_local_dir())
+                .to_path_buf(),
+        })
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join(CONFIG_FILENAME)
+    }
+
+    pub fn index_file(&self) -> PathBuf {
+        self.cache_dir.join(INDEX_FILENAME)
+    }
+
+    pub fn tombstones_file(&self) -> PathBuf {
+        self.state_dir.join(TOMBSTONES_FILENAME)
+    }
+
+    /// The lock serializing changes to vpm's files and project metadata.
+    pub fn lock_file(&self) -> PathBuf {
+        self.state_dir.join(LOCK_FILENAME)
+    }
+
+    /// Move files from the locations used by earlier versions to the platform directories,
+    /// printing a notice for each. Nothing moves while `$VPM_DATA_DIR` is set, so a one-off run
+    /// against another data dir leaves the usual files where they are.
+    pub fn migrate() -> Result<()> {
+        if data_dir_override().is_some() {
+            return Ok(());
+        }
+        let Some(home) = env::var_os("HOME").map(PathBuf::from) else {
+            return Ok(());
+        };
+        Self::platform()?.move_legacy_files(&home, |name| env::var(name).ok())
+    }
+
+    /// Earlier versions kept the config in `$XDG_CONFIG_HOME/vpm` and the index in
+    /// `$XDG_CACHE_HOME/vpm`, or under `~/.config` and `~/.cache`, on every platform. Pairs each
+    /// such file that exists with where it belongs now, unless something is there already.
+    fn legacy_moves(
+        &self,
+        home: &Path,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Vec<(PathBuf, PathBuf)> {
+        let dir = |name: &str, default: &str| {
+            var(name)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(default))
+                .join("vpm")
+        };
+        [
+            (
+                dir("XDG_CONFIG_HOME", ".config").join(CONFIG_FILENAME),
+                self.config_file(),
+            ),
+            (
+                dir("XDG_CACHE_HOME", ".cache").join(INDEX_FILENAME),
+                self.index_file(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(old, new)| old != new && old.exists() && !new.exists())
+        .collect()
+    }
+
+    fn move_legacy_files(&self, home: &Path, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for (old, new) in self.legacy_moves(home, var) {
+            if let Some(parent) = new.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&old, &new)?;
+            fs::remove_file(&old)?;
+            eprintln!("Moved {} to {}", old.display(), new.display());
+        }
+        Ok(())
+    }
+}
+
+/// `$VPM_DATA_DIR`, unless it is unset or empty.
+fn data_dir_override() -> Option<PathBuf> {
+    env::var_os("VPM_DATA_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{tree, TempDir};
+
+    fn platform(dir: &TempDir) -> Paths {
+        Paths {
+            config_dir: dir.path().join("Library/Preferences/vpm"),
+            cache_dir: dir.path().join("Library/Caches/vpm"),
+            state_dir: dir.path().join("Library/State/vpm"),
+        }
+    }
+
+    #[test]
+    fn moves_the_legacy_files_to_the_platform_directories() {
+        let dir = TempDir::new();
+        dir.write(".config/vpm/config.toml", "[editor]\ncommand = \"hx\"\n");
+        dir.write(".cache/vpm/index.toml", "");
+        let paths = platform(&dir);
+        paths.move_legacy_files(dir.path(), |_| None).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths.config_file()).unwrap(),
+            "[editor]\ncommand = \"hx\"\n"
+        );
+        assert!(paths.index_file().exists());
+        assert!(!dir.path().join(".config/vpm/config.toml").exists());
+        assert!(!dir.path().join(".cache/vpm/index.toml").exists());
+    }
+
+    #[test]
+    fn legacy_locations_follow_the_xdg_variables() {
+        let dir = TempDir::new();
+        let config = dir.write("xdg-config/vpm/config.toml", "");
+        dir.write(".config/vpm/config.toml", "");
+        let xdg = dir.path().join("xdg-config").display().to_string();
+        let var = |name: &str| (name == "XDG_CONFIG_HOME").then(|| xdg.clone());
+        let paths = platform(&dir);
+        assert_eq!(
+            paths.legacy_moves(dir.path(), var),
+            [(config, paths.config_file())]
+        );
+    }
+
+    #[test]
+    fn leaves_files_alone_when_the_new_location_is_taken_or_the_same() {
+        let dir = TempDir::new();
+        dir.write(".config/vpm/config.toml", "old");
+        let paths = platform(&dir);
+        dir.write("Library/Preferences/vpm/config.toml", "new");
+        assert!(paths.legacy_moves(dir.path(), |_| None).is_empty());
+
+        let linux = Paths {
+            config_dir: dir.path().join(".config/vpm"),
+            cache_dir: dir.path().join(".cache/vpm"),
+            state_dir: dir.path().join(".local/state/vpm"),
+        };
+        let before = tree(dir.path());
+        linux.move_legacy_files(dir.path(), |_| None).unwrap();
+        assert_eq!(tree(dir.path()), before);
+    }
+}
@@ -0,0 +1,314 @@
+use serde::Serialize;
+use std::{fs, io, path::Path};
+
+use crate::size;
+
+/// Directories holding build output or dependencies, shown as a file count instead of a tree.
+const ARTIFACT_DIRS: &[&str] = &[
+    ".git",
+    "target",
+    "node_modules",
+    "build",
+    "dist",
+    "__pycache__",
+    ".venv",
+    "venv",
+];
+/// How many of the largest files in each directory get their size printed.
+const SIZED_FILES: usize = 5;
+const README_NAMES: &[&str] = &[
+    "README.md",
+    "README",
+    "README.txt",
+    "README.rst",
+    "readme.md",
+];
+const PREVIEW_LINES: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    pub name: String,
+    pub kind: EntryKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Number of files in a collapsed artifact directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Entry>,
+}
+
+fn count_files(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| count_files(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(_) => 1,
+        Err(_) => 0,
+    }
+}
+
+/// The entries of `path` down to `depth` levels, directories first, without following symlinks.
+pub fn peek(path: impl AsRef<Path>, depth: usize) -> io::Result<Vec<Entry>> {
+    let mut entries = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = fs::symlink_metadata(&path)?;
+            let mut entry = Entry {
+                name,
+                kind: EntryKind::File,
+                size: None,
+                target: None,
+                files: None,
+                children: Vec::new(),
+            };
+            if metadata.is_symlink() {
+                entry.kind = EntryKind::Symlink;
+                entry.target = fs::read_link(&path)
+                    .ok()
+                    .map(|target| target.to_string_lossy().into_owned());
+            } else if metadata.is_dir() {
+                entry.kind = EntryKind::Dir;
+                if ARTIFACT_DIRS.contains(&entry.name.as_str()) {
+                    entry.files = Some(count_files(&path));
+                } else if depth > 1 {
+                    entry.children = peek(&path, depth - 1)?;
+                }
+            } else {
+                entry.size = Some(metadata.len());
+            }
+            Ok(entry)
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by(|a, b| {
+        (a.kind != EntryKind::Dir)
+            .cmp(&(b.kind != EntryKind::Dir))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(entries)
+}
+
+/// Abbreviate a count, e.g. `12k`.
+fn format_count(count: u64) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{}k", count / 1_000),
+        _ => format!("{}M", count / 1_000_000),
+    }
+}
+
+/// Render `entries` as an indented tree, one line per entry.
+pub fn render(entries: &[Entry]) -> Vec<String> {
+    let mut lines = Vec::new();
+    render_into(entries, "", &mut lines);
+    lines
+}
+
+fn render_into(entries: &[Entry], indent: &str, lines: &mut Vec<String>) {
+    let mut sizes = entries
+        .iter()
+        .filter_map(|entry| entry.size)
+        .collect::<Vec<_>>();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    let min_shown = sizes.get(SIZED_FILES - 1).copied().unwrap_or(0);
+    for (i, entry) in entries.iter().enumerate() {
+        let last = i + 1 == entries.len();
+        let branch = if last { "└── " } else { "├── " };
+        let label = match entry.kind {
+            EntryKind::Dir => match entry.files {
+                Some(files) => format!("{}/ ({} files)", entry.name, format_count(files)),
+                None => format!("{}/", entry.name),
+            },
+            EntryKind::Symlink => format!(
+                "{} -> {}",
+                entry.name,
+                entry.target.as_deref().unwrap_or("?")
+            ),
+            EntryKind::File => match entry.size {
+                Some(size) if size >= min_shown && size > 0 => {
+                    format!("{}  {}", entry.name, size::format_size(size))
+                }
+                _ => entry.name.clone(),
+            },
+        };
+        lines.push(format!("{indent}{branch}{label}"));
+        let child_indent = format!("{indent}{}", if last { "    " } else { "│   " });
+        render_into(&entry.children, &child_indent, lines);
+    }
+}
+
+/// Lines previewing a project: the start of its README, or its top-level tree without one.
+pub fn preview(path: impl AsRef<Path>) -> Vec<String> {
+    let path = path.as_ref();
+    let readme = README_NAMES
+        .iter()
+        .find_map(|name| fs::read_to_string(path.join(name)).ok());
+    match readme {
+        Some(readme) => readme
+            .lines()
+            .take(PREVIEW_LINES)
+            .map(str::to_string)
+            .collect(),
+        None => peek(path, 1)
+            .map(|entries| render(&entries))
+            .unwrap_or_else(|err| vec![err.to_string()]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn file(name: &str, size: u64) -> Entry {
+        Entry {
+            name: name.to_string(),
+            kind: EntryKind::File,
+            size: Some(size),
+            target: None,
+            files: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn dir(name: &str, children: Vec<Entry>) -> Entry {
+        Entry {
+            kind: EntryKind::Dir,
+            size: None,
+            children,
+            ..file(name, 0)
+        }
+    }
+
+    #[test]
+    fn artifact_dirs_collapse_into_a_file_count() {
+        let project = TempDir::new();
+        project.write("target/debug/app", "");
+        project.write("target/debug/deps/a.rlib", "");
+        project.write("target/CACHEDIR.TAG", "");
+        project.write("src/main.rs", "fn main() {}\n");
+        let entries = peek(project.path(), 3).unwrap();
+        assert_eq!(entries[0].name, "src");
+        assert_eq!(entries[0].children, [file("main.rs", 13)]);
+        assert_eq!(
+            entries[1],
+            Entry {
+                files: Some(3),
+                ..dir("target", Vec::new())
+            }
+        );
+    }
+
+    #[test]
+    fn peeking_stops_at_the_depth_and_lists_dirs_first() {
+        let project = TempDir::new();
+        project.write("a.txt", "");
+        project.write("z/deep/file.txt", "");
+        project.mkdir("b");
+        let entries = peek(project.path(), 2).unwrap();
+        assert_eq!(
+            entries,
+            [
+                dir("b", Vec::new()),
+                dir("z", vec![dir("deep", Vec::new())]),
+                file("a.txt", 0),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_shown_but_not_followed() {
+        let project = TempDir::new();
+        project.write("real/file.txt", "");
+        std::os::unix::fs::symlink("real", project.path().join("link")).unwrap();
+        let entries = peek(project.path(), 3).unwrap();
+        assert_eq!(entries[1].kind, EntryKind::Symlink);
+        assert_eq!(entries[1].target.as_deref(), Some("real"));
+        assert!(entries[1].children.is_empty());
+    }
+
+    #[test]
+    fn rendering_draws_the_tree() {
+        let entries = [
+            dir("src", vec![file("lib.rs", 0), file("main.rs", 0)]),
+            Entry {
+                files: Some(12_345),
+                ..dir("node_modules", Vec::new())
+            },
+            file("README.md", 0),
+        ];
+        assert_eq!(
+            render(&entries),
+            [
+                "├── src/",
+                "│   ├── lib.rs",
+                "│   └── main.rs",
+                "├── node_modules/ (12k files)",
+                "└── README.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_largest_files_show_their_size() {
+        let entries = (1..=7)
+            .map(|i| file(&format!("f{i}"), i * 1024))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            render(&entries),
+            [
+                "├── f1",
+                "├── f2",
+                "├── f3  3.0 KiB",
+                "├── f4  4.0 KiB",
+                "├── f5  5.0 KiB",
+                "├── f6  6.0 KiB",
+                "└── f7  7.0 KiB",
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_are_abbreviated() {
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_000), "1k");
+        assert_eq!(format_count(999_999), "999k");
+        assert_eq!(format_count(1_500_000), "1M");
+    }
+
+    #[test]
+    fn previews_truncate_the_readme() {
+        let project = TempDir::new();
+        let readme = (1..=50).map(|i| format!("line {i}\n")).collect::<String>();
+        project.write("README.md", &readme);
+        let preview = preview(project.path());
+        assert_eq!(preview.len(), PREVIEW_LINES);
+        assert_eq!(preview.last().unwrap(), "line 40");
+    }
+
+    #[test]
+    fn previews_without_a_readme_show_the_top_level() {
+        let project = TempDir::new();
+        project.write("src/main.rs", "");
+        project.write("Cargo.toml", "");
+        assert_eq!(preview(project.path()), ["├── src/", "└── Cargo.toml"]);
+    }
+}
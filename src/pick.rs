@@ -0,0 +1,126 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+
+/// Parse a selection like `1,3-5` of items numbered from 1 to `count` into sorted, distinct
+/// 0-based indices.
+pub fn parse_selection(input: &str, count: usize) -> Result<Vec<usize>> {
+    let number = |text: &str| -> Result<usize> {
+        let number = text
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| anyhow!("`{}` is not a number", text.trim()))?;
+        if number == 0 || number > count {
+            return Err(anyhow!("{number} is not between 1 and {count}"));
+        }
+        Ok(number - 1)
+    };
+    let mut selected = BTreeSet::new();
+    for part in input.split(',').filter(|part| !part.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (number(start)?, number(end)?);
+                if start > end {
+                    return Err(anyhow!("The range `{}` is backwards", part.trim()));
+                }
+                selected.extend(start..=end);
+            }
+            None => {
+                selected.insert(number(part)?);
+            }
+        }
+    }
+    if selected.is_empty() {
+        return Err(anyhow!("Nothing was selected"));
+    }
+    Ok(selected.into_iter().collect())
+}
+
+/// Where the answers come from: stdin when it's a terminal, else the controlling terminal so
+/// that pickers also work with piped stdin. The menu goes to stderr, which must be a terminal.
+fn answers() -> Result<Box<dyn BufRead>> {
+    if !io::stderr().is_terminal() {
+        return Err(anyhow!("Picking needs a terminal, pass the IDs instead"));
+    }
+    if io::stdin().is_terminal() {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let tty = File::open("/dev/tty")
+        .map_err(|_| anyhow!("Picking needs a terminal, pass the IDs instead"))?;
+    Ok(Box::new(BufReader::new(tty)))
+}
+
+/// Number `items` on stderr and ask for a selection until it's valid.
+fn ask(question: &str, items: &[String], multiple: bool) -> Result<Vec<usize>> {
+    if items.is_empty() {
+        return Err(anyhow!("There is nothing to pick from"));
+    }
+    let mut answers = answers()?;
+    let width = items.len().to_string().len();
+    for (number, item) in items.iter().enumerate() {
+        eprintln!("{:>width$}) {item}", number + 1);
+    }
+    let hint = if multiple { "e.g. 1,3-5" } else { "a number" };
+    loop {
+        eprint!("{question} ({hint}, empty to cancel): ");
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        if answers.read_line(&mut answer)? == 0 || answer.trim().is_empty() {
+            return Err(anyhow!("Nothing was picked"));
+        }
+        match parse_selection(&answer, items.len()) {
+            Ok(selected) if multiple || selected.len() == 1 => return Ok(selected),
+            Ok(_) => eprintln!("Pick a single item"),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+}
+
+/// Let the user pick one of `items`, returning its index.
+pub fn select_one(question: &str, items: &[String]) -> Result<usize> {
+    Ok(ask(question, items, false)?[0])
+}
+
+/// Let the user pick any number of `items`, returning their indices in order.
+pub fn select_many(question: &str, items: &[String]) -> Result<Vec<usize>> {
+    ask(question, items, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lists_and_ranges() {
+        let cases = [
+            ("1-3,5", vec![0, 1, 2, 4]),
+            ("5, 1-3", vec![0, 1, 2, 4]),
+            ("2", vec![1]),
+            ("2-2", vec![1]),
+            ("1-3,2-4,,", vec![0, 1, 2, 3]),
+            (" 5 - 5 ", vec![4]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_selection(input, 5).unwrap(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_selections() {
+        let cases = [
+            ("3-1", "The range `3-1` is backwards"),
+            ("0", "0 is not between 1 and 5"),
+            ("6", "6 is not between 1 and 5"),
+            ("4-6", "6 is not between 1 and 5"),
+            ("a", "`a` is not a number"),
+            ("1-", "`` is not a number"),
+            ("", "Nothing was selected"),
+            (" , ", "Nothing was selected"),
+        ];
+        for (input, expected) in cases {
+            let err = parse_selection(input, 5).unwrap_err().to_string();
+            assert_eq!(err, expected, "{input}");
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    confirm, editor, error::VpmError, format_name, fsops::ScratchDir, rename_project, Project,
+};
+
+const HEADER: &str = "\
+# Edit the names of the projects below and save the file to rename them.
+# Each line is `<id>\\t<name>`. Deleted lines are left unchanged.
+";
+
+/// An error in the rename buffer, pointing at the offending line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+pub fn render_buffer(projects: &BTreeMap<usize, Project>) -> String {
+    let mut buffer = HEADER.to_string();
+    for project in projects.values() {
+        buffer.push_str(&format!("{}\t{}\n", project.id, project.name));
+    }
+    buffer
+}
+
+/// Parse the edited buffer into `(id, name)` pairs, skipping blank and comment lines.
+pub fn parse_buffer(buffer: &str) -> Result<Vec<(usize, String)>, ParseError> {
+    buffer
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            let error = |message: &str| ParseError {
+                line: index + 1,
+                message: message.to_string(),
+            };
+            let (id, name) = line
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error("expected `<id>\\t<name>`"))?;
+            let id = id.parse().map_err(|_| error("invalid project id"))?;
+            let name = format_name(name).map_err(|err| error(&err))?;
+            Ok((id, name))
+        })
+        .collect()
+}
+
+/// Compute the renames requested by the parsed buffer, checking for unknown ids and collisions.
+pub fn plan_renames(
+    entries: &[(usize, String)],
+    projects: &BTreeMap<usize, Project>,
+) -> Result<Vec<(usize, Project)>> {
+    let mut seen = HashSet::new();
+    let mut targets = HashSet::new();
+    let mut plan = Vec::new();
+    for (id, name) in entries {
+        let project = projects
+            .get(id)
+            .ok_or(VpmError::ProjectNotFound { id: *id })?;
+        if !seen.insert(*id) {
+            return Err(anyhow!("Project {id} appears more than once!"));
+        }
+        if *name == project.name {
+            continue;
+        }
+        let new_project = Project::new(
+            &project.root,
+            *id,
+            name,
+            project.date,
+            project.last_accessed,
+        )
+        .with_status(project.status)
+        .with_id_width(project.id_width);
+        let new_path = new_project.get_path();
+        if Path::new(&new_path).exists() || !targets.insert(new_path.clone()) {
+            return Err(anyhow!("A project already exists at {new_path}!"));
+        }
+        plan.push((*id, new_project));
+    }
+    Ok(plan)
+}
+
+pub fn edit(projects: &BTreeMap<usize, Project>) -> Result<()> {
+    let scratch = ScratchDir::new("vpm-rename")?;
+    let buffer_path = scratch.create_file("rename.txt", &render_buffer(projects))?;
+    let entries = loop {
+        editor::edit_file(&buffer_path)?;
+        match parse_buffer(&fs::read_to_string(&buffer_path)?) {
+            Ok(entries) => break entries,
+            Err(err) => {
+                eprintln!("Line {}: {}", err.line, err.message);
+                if !confirm("Re-open the editor")? {
+                    return Ok(());
+                }
+            }
+        }
+    };
+    drop(scratch);
+
+    let plan = plan_renames(&entries, projects)?;
+    if plan.is_empty() {
+        println!("Nothing to rename");
+        return Ok(());
+    }
+    for (id, new_project) in plan.iter() {
+        println!(
+            "{} -> {}",
+            projects[id].full_name(),
+            new_project.full_name()
+        );
+    }
+    if !confirm(&format!("Rename {} projects", plan.len()))? {
+        println!("Aborting...");
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for (id, new_project) in plan.iter() {
+        match rename_project(&projects[id], new_project) {
+            Ok(()) => println!("Renamed project: {}", new_project.full_name()),
+            Err(err) => {
+                failures += 1;
+                eprintln!("Failed to rename {}: {err}", projects[id].full_name());
+            }
+        }
+    }
+    println!("{} renamed, {} failed", plan.len() - failures, failures);
+    if failures > 0 {
+        return Err(anyhow!("{failures} renames failed"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use chrono::{Local, NaiveDate};
+
+    /// Projects named `names` under the project home `dir`, with ids from 1.
+    fn projects(dir: &TempDir, names: &[&str]) -> BTreeMap<usize, Project> {
+        let root = dir.path().to_string_lossy();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (1..)
+            .zip(names)
+            .map(|(id, name)| {
+                (
+                    id,
+                    Project::new(root.clone(), id, *name, date, Local::now()),
+                )
+            })
+            .collect()
+    }
+
+    fn entries(pairs: &[(usize, &str)]) -> Vec<(usize, String)> {
+        pairs
+            .iter()
+            .map(|&(id, name)| (id, name.to_string()))
+            .collect()
+    }
+
+    fn renamed(plan: &[(usize, Project)]) -> Vec<(usize, String)> {
+        plan.iter()
+            .map(|(id, project)| (*id, project.full_name()))
+            .collect()
+    }
+
+    #[test]
+    fn the_rendered_buffer_parses_back() {
+        let projects = projects(&TempDir::new(), &["app", "notes"]);
+        let buffer = render_buffer(&projects);
+        assert_eq!(
+            parse_buffer(&buffer).unwrap(),
+            entries(&[(1, "app"), (2, "notes")])
+        );
+    }
+
+    #[test]
+    fn parsing_skips_comments_and_blank_lines_and_formats_names() {
+        let buffer = "# header\n\n  # indented\n1\tMy App\n2   spaced  out \n";
+        assert_eq!(
+            parse_buffer(buffer).unwrap(),
+            entries(&[(1, "my-app"), (2, "spaced-out")])
+        );
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_line() {
+        for (buffer, line, message) in [
+            ("# header\n1\n", 2, "expected `<id>\\t<name>`"),
+            ("x\tapp\n", 1, "invalid project id"),
+            (
+                "1\tapp\n\n2\ta/b\n",
+                3,
+                "Name must not contain path separators!",
+            ),
+        ] {
+            let err = parse_buffer(buffer).unwrap_err();
+            assert_eq!(
+                err,
+                ParseError {
+                    line,
+                    message: message.to_string()
+                },
+                "{buffer:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unchanged_and_deleted_lines_are_left_alone() {
+        let projects = projects(&TempDir::new(), &["app", "notes", "site"]);
+        let plan = plan_renames(&entries(&[(1, "app"), (3, "blog")]), &projects).unwrap();
+        assert_eq!(renamed(&plan), [(3, "p03-blog-2024-01-01".to_string())]);
+    }
+
+    #[test]
+    fn names_can_be_swapped_or_shared() {
+        let projects = projects(&TempDir::new(), &["app", "notes"]);
+        // The id keeps the folders apart, so neither is a collision.
+        for pairs in [[(1, "notes"), (2, "app")], [(1, "shared"), (2, "shared")]] {
+            let plan = plan_renames(&entries(&pairs), &projects).unwrap();
+            assert_eq!(
+                renamed(&plan),
+                pairs
+                    .map(|(id, name)| (id, format!("p{id:02}-{name}-2024-01-01")))
+                    .to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_and_unknown_ids_are_refused() {
+        let projects = projects(&TempDir::new(), &["app"]);
+        let err = plan_renames(&entries(&[(1, "a"), (1, "b")]), &projects).unwrap_err();
+        assert_eq!(err.to_string(), "Project 1 appears more than once!");
+        let err = plan_renames(&entries(&[(7, "a")]), &projects).unwrap_err();
+        assert_eq!(err.to_string(), "Project 7 not found!");
+    }
+
+    #[test]
+    fn existing_folders_are_not_overwritten() {
+        let dir = TempDir::new();
+        let target = dir.mkdir("Active/p01-taken-2024-01-01");
+        let projects = projects(&dir, &["app"]);
+        let err = plan_renames(&entries(&[(1, "taken")]), &projects).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("A project already exists at {}!", target.display())
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Runs an expensive probe for the key the user has settled on, at most once per key.
+///
+/// Requests are debounced so that only a key which stayed requested for `debounce` is probed,
+/// results are cached for the lifetime of the scheduler, and probes run on a fixed pool of
+/// worker threads.
+pub struct Scheduler<K, V, C = SystemClock> {
+    clock: C,
+    debounce: Duration,
+    pending: Option<(K, Instant)>,
+    in_flight: HashSet<K>,
+    results: HashMap<K, V>,
+    jobs: Sender<K>,
+    finished: Receiver<(K, V)>,
+}
+
+impl<K, V> Scheduler<K, V>
+where
+    K: Clone + Eq + Hash + Send + 'static,
+    V: Send + 'static,
+{
+    pub fn new(
+        workers: usize,
+        debounce: Duration,
+        probe: impl Fn(&K) -> V + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_clock(SystemClock, workers, debounce, probe)
+    }
+}
+
+impl<K, V, C> Scheduler<K, V, C>
+where
+    K: Clone + Eq + Hash + Send + 'static,
+    V: Send + 'static,
+    C: Clock,
+{
+    pub fn with_clock(
+        clock: C,
+        workers: usize,
+        debounce: Duration,
+        probe: impl Fn(&K) -> V + Send + Sync + 'static,
+    ) -> Self {
+        let (jobs, job_queue) = mpsc::channel::<K>();
+        let (results, finished) = mpsc::channel();
+        let job_queue = Arc::new(Mutex::new(job_queue));
+        let probe = Arc::new(probe);
+        for _ in 0..workers.max(1) {
+            let job_queue = Arc::clone(&job_queue);
+            let results = results.clone();
+            let probe = Arc::clone(&probe);
+            thread::spawn(move || loop {
+                let job = job_queue.lock().map(|queue| queue.recv());
+                let Ok(Ok(key)) = job else {
+                    break;
+                };
+                let value = probe(&key);
+                if results.send((key, value)).is_err() {
+                    break;
+                }
+            });
+        }
+        Self {
+            clock,
+            debounce,
+            pending: None,
+            in_flight: HashSet::new(),
+            results: HashMap::new(),
+            jobs,
+            finished,
+        }
+    }
+
+    /// Ask for `key` to be probed once it has stayed requested for the debounce period.
+    pub fn request(&mut self, key: K) {
+        if self.results.contains_key(&key) || self.in_flight.contains(&key) {
+            self.pending = None;
+            return;
+        }
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|(pending, _)| *pending == key)
+        {
+            return;
+        }
+        self.pending = Some((key, self.clock.now()));
+    }
+
+    /// Collect finished probes and start the pending one if it has rested long enough.
+    pub fn tick(&mut self) {
+        while let Ok((key, value)) = self.finished.try_recv() {
+            self.in_flight.remove(&key);
+            self.results.insert(key, value);
+        }
+        let rested = self
+            .pending
+            .as_ref()
+            .is_some_and(|(_, since)| self.clock.now().duration_since(*since) >= self.debounce);
+        if rested {
+            if let Some((key, _)) = self.pending.take() {
+                self.in_flight.insert(key.clone());
+                let _ = self.jobs.send(key);
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.results.get(key)
+    }
+
+    /// Whether a probe is waiting for its debounce period or still running.
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some() || !self.in_flight.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::Cell,
+        rc::Rc,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    /// A clock that only moves when the test says so.
+    #[derive(Clone)]
+    struct MockClock(Rc<Cell<Instant>>);
+
+    impl MockClock {
+        fn new() -> Self {
+            Self(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, by: Duration) {
+            self.0.set(self.0.get() + by);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    /// A scheduler whose probe doubles the key and counts how often it ran.
+    fn counting(workers: usize) -> (Scheduler<u32, u32, MockClock>, MockClock, Arc<AtomicUsize>) {
+        let clock = MockClock::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let scheduler = Scheduler::with_clock(clock.clone(), workers, DEBOUNCE, move |key| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            key * 2
+        });
+        (scheduler, clock, calls)
+    }
+
+    /// Tick until nothing is in flight anymore.
+    fn settle<C: Clock>(scheduler: &mut Scheduler<u32, u32, C>) {
+        for _ in 0..500 {
+            scheduler.tick();
+            if scheduler.in_flight.is_empty() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        panic!("probes did not finish");
+    }
+
+    #[test]
+    fn waits_for_the_debounce_period() {
+        let (mut scheduler, clock, calls) = counting(1);
+        scheduler.request(1);
+        scheduler.tick();
+        assert!(scheduler.is_busy());
+        clock.advance(DEBOUNCE / 2);
+        scheduler.tick();
+        assert!(scheduler.in_flight.is_empty());
+        clock.advance(DEBOUNCE / 2);
+        scheduler.tick();
+        settle(&mut scheduler);
+        assert_eq!(scheduler.get(&1), Some(&2));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!scheduler.is_busy());
+    }
+
+    #[test]
+    fn moving_on_restarts_the_debounce() {
+        let (mut scheduler, clock, calls) = counting(1);
+        scheduler.request(1);
+        clock.advance(DEBOUNCE / 2);
+        scheduler.request(2);
+        clock.advance(DEBOUNCE / 2);
+        scheduler.tick();
+        settle(&mut scheduler);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        clock.advance(DEBOUNCE / 2);
+        scheduler.tick();
+        settle(&mut scheduler);
+        assert_eq!(scheduler.get(&1), None);
+        assert_eq!(scheduler.get(&2), Some(&4));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn repeated_requests_keep_their_start_time() {
+        let (mut scheduler, clock, _) = counting(1);
+        scheduler.request(1);
+        clock.advance(DEBOUNCE / 2);
+        scheduler.request(1);
+        clock.advance(DEBOUNCE / 2);
+        scheduler.tick();
+        settle(&mut scheduler);
+        assert_eq!(scheduler.get(&1), Some(&2));
+    }
+
+    #[test]
+    fn probes_each_key_once() {
+        let (mut scheduler, clock, calls) = counting(2);
+        for _ in 0..3 {
+            scheduler.request(1);
+            clock.advance(DEBOUNCE);
+            scheduler.tick();
+            // Still in flight, or already cached: either way it isn't queued again.
+            scheduler.request(1);
+            assert!(scheduler.pending.is_none());
+            settle(&mut scheduler);
+        }
+        assert_eq!(scheduler.get(&1), Some(&2));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!scheduler.is_busy());
+    }
+
+    #[test]
+    fn runs_at_most_as_many_probes_as_workers() {
+        let clock = MockClock::new();
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (probe_running, probe_most) = (Arc::clone(&running), Arc::clone(&most));
+        let mut scheduler = Scheduler::with_clock(clock.clone(), 2, DEBOUNCE, move |key: &u32| {
+            let now = probe_running.fetch_add(1, Ordering::SeqCst) + 1;
+            probe_most.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            probe_running.fetch_sub(1, Ordering::SeqCst);
+            *key
+        });
+        for key in 0..6 {
+            scheduler.request(key);
+            clock.advance(DEBOUNCE);
+            scheduler.tick();
+        }
+        assert_eq!(scheduler.in_flight.len() + scheduler.results.len(), 6);
+        settle(&mut scheduler);
+        assert!((0..6).all(|key| scheduler.get(&key) == Some(&key)));
+        assert!((1..=2).contains(&most.load(Ordering::SeqCst)));
+    }
+}
@@ -1,7 +1,17 @@
-use color_eyre::eyre::Result;
+use clap_complete::Shell;
+use color_eyre::eyre::{anyhow, Result};
 use std::fs::File;
 use std::io::ErrorKind;
-use std::{env, io::Write, path::PathBuf};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{atomic, confirm};
+
+/// Marks the files vpm owns, so they can be replaced or removed without asking.
+const SENTINEL: &str = "# Managed by vpm, changes to this file will be overwritten.";
 
 const FISH_PJ_FILENAME: &str = ".config/fish/functions/pj.fish";
 const FISH_PJ_FUNCTION: &str = r#"
@@ -20,7 +30,7 @@ const FISH_J_FUNCTION: &str = r#"
 # Usage: j <QUERY>
 # Example: j some-proj
 function j
-    set path (vpm -p search -l 1 (echo $argv))
+    set path (vpm -p search -l 1 --min-score 40 (echo $argv))
     if set -q path[1]
         cd $path
     else
@@ -29,8 +39,18 @@ function j
 end
 "#;
 pub fn init_fish() -> Result<()> {
-    bind_functions(FISH_J_FILENAME, FISH_J_FUNCTION)?;
-    bind_functions(FISH_PJ_FILENAME, FISH_PJ_FUNCTION)?;
+    let home = PathBuf::from(env::var("HOME")?);
+    for (filename, function) in [
+        (FISH_J_FILENAME, FISH_J_FUNCTION),
+        (FISH_PJ_FILENAME, FISH_PJ_FUNCTION),
+    ] {
+        let path = home.join(filename);
+        if write_managed(&path, function)? {
+            println!("Wrote {}", path.display());
+        } else {
+            println!("Skipped {}", path.display());
+        }
+    }
     Ok(())
 }
 
@@ -51,7 +71,7 @@ pj() {
 # Usage: j <QUERY>
 # Example: j some-proj
 j() {
-    project_path=$(vpm -p search -l 1 $1)
+    project_path=$(vpm -p search -l 1 --min-score 40 $1)
     if [ -z "$project_path" ]; then
         echo "No project found"
         return 1
@@ -64,16 +84,112 @@ pub fn init_zsh() -> Result<()> {
     Ok(())
 }
 
+const BASH_FILENAME: &str = ".bashrc";
+const BASH_FUNCTIONS: &str = r#"
+# cd into a project by its ID.
+# Usage: pj <ID>
+# Example: pj 1
+pj() {
+    local project_path
+    project_path=$(vpm path "$1")
+    if [ -z "$project_path" ]; then
+        return 1
+    fi
+    cd "$project_path"
+}
+
+# Fuzzy search for a project and cd into it.
+# Usage: j <QUERY>
+# Example: j some-proj
+j() {
+    local project_path
+    project_path=$(vpm -p search -l 1 --min-score 40 "$1")
+    if [ -z "$project_path" ]; then
+        echo "No project found"
+        return 1
+    fi
+    cd "$project_path"
+}
+"#;
+pub fn init_bash() -> Result<()> {
+    bind_functions(BASH_FILENAME, BASH_FUNCTIONS)?;
+    Ok(())
+}
+
+const NUSHELL_FILENAME: &str = ".config/nushell/autoload/vpm.nu";
+const NUSHELL_FUNCTIONS: &str = r#"
+# cd into a project by its ID.
+# Usage: pj <ID>
+# Example: pj 1
+def --env pj [id: int] {
+    let project_path = (^vpm path $id | str trim)
+    if ($project_path | is-not-empty) {
+        cd $project_path
+    }
+}
+
+# Fuzzy search for a project and cd into it.
+# Usage: j <QUERY>
+# Example: j some-proj
+def --env j [query: string] {
+    let project_path = (^vpm -p search -l 1 --min-score 40 $query | str trim)
+    if ($project_path | is-empty) {
+        print $"No project was found for query: ($query)"
+    } else {
+        cd $project_path
+    }
+}
+"#;
+pub fn init_nushell() -> Result<()> {
+    let path = PathBuf::from(env::var("HOME")?).join(NUSHELL_FILENAME);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if write_managed(&path, NUSHELL_FUNCTIONS)? {
+        println!("Wrote {}", path.display());
+    } else {
+        println!("Skipped {}", path.display());
+    }
+    Ok(())
+}
+
+/// Shells `vpm init` knows how to set up.
+pub const SUPPORTED_SHELLS: &[&str] = &["fish", "zsh", "bash", "nu"];
+
+/// Guess the shell name from `$SHELL`, falling back to the parent process name.
+/// Handles paths, login shells (`-zsh`) and wrappers like `/usr/bin/env bash`.
+pub fn detect_shell(shell_var: Option<&str>, parent_process: Option<&str>) -> Option<String> {
+    let name = |value: &str| {
+        let program = value.split_whitespace().last()?;
+        let program = program.rsplit('/').next()?.trim_start_matches('-');
+        (!program.is_empty()).then(|| program.to_lowercase())
+    };
+    shell_var
+        .and_then(name)
+        .or_else(|| parent_process.and_then(name))
+}
+
+/// Name of the process that started vpm, where the platform exposes it.
+#[cfg(unix)]
+pub fn parent_process() -> Option<String> {
+    let ppid = std::os::unix::process::parent_id();
+    fs::read_to_string(format!("/proc/{ppid}/comm"))
+        .ok()
+        .map(|comm| comm.trim().to_string())
+}
+
+#[cfg(not(unix))]
+pub fn parent_process() -> Option<String> {
+    None
+}
+
 fn bind_functions(filename: &str, functions: &str) -> Result<()> {
     let function_path = PathBuf::from(env::var("HOME")?).join(filename);
-    print!(
-        "This will create or open the file at {:?} and append the functions to it.\nDo you want to continue [y/N]? ",
+    println!(
+        "This will create or open the file at {:?} and append the functions to it.",
         function_path
     );
-    std::io::stdout().flush()?;
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
-    if input.trim().to_lowercase() != "y" {
+    if !confirm("Do you want to continue")? {
         println!("Aborting...");
         return Ok(());
     }
@@ -88,3 +204,254 @@ fn bind_functions(filename: &str, functions: &str) -> Result<()> {
     println!("Done!");
     Ok(())
 }
+
+/// Whether the file at `path` was written by vpm.
+fn is_managed(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|contents| contents.contains(SENTINEL))
+}
+
+/// Atomically write `contents` and the sentinel to `path`. A file vpm didn't write is only
+/// replaced after confirmation. Returns whether the file was written.
+fn write_managed(path: &Path, contents: &str) -> Result<bool> {
+    if path.exists() && !is_managed(path) {
+        println!("{} exists and was not written by vpm.", path.display());
+        if !confirm("Do you want to overwrite it")? {
+            return Ok(false);
+        }
+    }
+    atomic::write(path, format!("{}\n{SENTINEL}\n", contents.trim_end()))?;
+    Ok(true)
+}
+
+/// Where `shell` looks for the user's completion script for vpm, given `$HOME` and a lookup of
+/// the other environment variables. `None` for shells without a per-user completion directory.
+pub fn completions_path(
+    shell: Shell,
+    home: &Path,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    let dir = |name: &str, default: &str| {
+        var(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(default))
+    };
+    match shell {
+        Shell::Fish => Some(
+            dir("XDG_CONFIG_HOME", ".config")
+                .join("fish/completions")
+                .join("vpm.fish"),
+        ),
+        Shell::Zsh => Some(dir("ZDOTDIR", "").join(".zfunc").join("_vpm")),
+        Shell::Bash => Some(
+            dir("XDG_DATA_HOME", ".local/share")
+                .join("bash-completion/completions")
+                .join("vpm"),
+        ),
+        _ => None,
+    }
+}
+
+fn home_completions_path(shell: Shell) -> Result<Option<PathBuf>> {
+    let home = PathBuf::from(env::var("HOME")?);
+    Ok(completions_path(shell, &home, |name| env::var(name).ok()))
+}
+
+/// Install `script` where `shell` picks it up. If that isn't possible, print it with instructions.
+pub fn install_completions(shell: Shell, script: &str) -> Result<()> {
+    let Some(path) = home_completions_path(shell)? else {
+        eprintln!("vpm can't install completions for {shell}, load this script from your shell's configuration instead:");
+        print!("{script}");
+        return Ok(());
+    };
+    match write_managed(&path, script) {
+        Ok(true) => {}
+        Ok(false) => {
+            println!("Aborting...");
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!(
+                "Could not write {}: {err}\nSave this script there yourself:",
+                path.display()
+            );
+            print!("{script}");
+            return Ok(());
+        }
+    }
+    println!("Installed completions to {}", path.display());
+    match shell {
+        Shell::Fish => println!("fish will load them the next time you complete vpm."),
+        Shell::Zsh => {
+            let dir = path.parent().unwrap_or(&path).display();
+            println!("Start a new shell session to use them.");
+            println!("{dir} must be in your fpath before compinit runs, e.g. add `fpath+=({dir})` to your .zshrc.");
+        }
+        _ => println!("Start a new shell session to use them."),
+    }
+    Ok(())
+}
+
+pub fn uninstall_completions(shell: Shell) -> Result<()> {
+    let Some(path) = home_completions_path(shell)? else {
+        return Err(anyhow!("vpm doesn't install completions for {shell}"));
+    };
+    if !path.exists() {
+        println!("No completions installed at {}", path.display());
+        return Ok(());
+    }
+    if !is_managed(&path) {
+        return Err(anyhow!(
+            "{} was not written by vpm, leaving it alone",
+            path.display()
+        ));
+    }
+    fs::remove_file(&path)?;
+    println!("Removed {}", path.display());
+    Ok(())
+}
+
+/// The variable `vpm path --format-shell` exports for the project `name`: `VPM_PROJECT_` and
+/// the name uppercased, with `-` and any other character not allowed in a variable as `_`.
+pub fn to_env_var_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("VPM_PROJECT_{name}")
+}
+
+/// A POSIX shell line exporting the path of the project `name`.
+pub fn export_line(name: &str, path: &str) -> String {
+    format!(
+        "export {}='{}'",
+        to_env_var_name(name),
+        path.replace('\'', r"'\''")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_shell_from_shell_var() {
+        let cases = [
+            ("fish", "fish"),
+            ("/usr/bin/fish", "fish"),
+            ("/bin/zsh", "zsh"),
+            ("-zsh", "zsh"),
+            ("/bin/bash", "bash"),
+            ("/usr/bin/env bash", "bash"),
+            ("/opt/homebrew/bin/Nu", "nu"),
+        ];
+        for (shell, expected) in cases {
+            assert_eq!(
+                detect_shell(Some(shell), None).as_deref(),
+                Some(expected),
+                "{shell}"
+            );
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_parent_process() {
+        assert_eq!(detect_shell(None, Some("zsh")).as_deref(), Some("zsh"));
+        assert_eq!(
+            detect_shell(Some(""), Some("-bash")).as_deref(),
+            Some("bash")
+        );
+        assert_eq!(
+            detect_shell(Some("/"), Some("fish")).as_deref(),
+            Some("fish")
+        );
+        assert_eq!(
+            detect_shell(Some("fish"), Some("bash")).as_deref(),
+            Some("fish")
+        );
+        assert_eq!(detect_shell(None, None), None);
+    }
+
+    fn path_for(shell: Shell, vars: &[(&str, &str)]) -> Option<PathBuf> {
+        completions_path(shell, Path::new("/home/me"), |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn resolves_the_default_completion_directories() {
+        let cases = [
+            (Shell::Fish, "/home/me/.config/fish/completions/vpm.fish"),
+            (Shell::Zsh, "/home/me/.zfunc/_vpm"),
+            (
+                Shell::Bash,
+                "/home/me/.local/share/bash-completion/completions/vpm",
+            ),
+        ];
+        for (shell, expected) in cases {
+            assert_eq!(
+                path_for(shell, &[]),
+                Some(PathBuf::from(expected)),
+                "{shell}"
+            );
+        }
+    }
+
+    #[test]
+    fn honours_the_shell_directory_variables() {
+        let vars = [
+            ("XDG_CONFIG_HOME", "/config"),
+            ("XDG_DATA_HOME", "/data"),
+            ("ZDOTDIR", "/zsh"),
+        ];
+        let cases = [
+            (Shell::Fish, "/config/fish/completions/vpm.fish"),
+            (Shell::Zsh, "/zsh/.zfunc/_vpm"),
+            (Shell::Bash, "/data/bash-completion/completions/vpm"),
+        ];
+        for (shell, expected) in cases {
+            assert_eq!(
+                path_for(shell, &vars),
+                Some(PathBuf::from(expected)),
+                "{shell}"
+            );
+        }
+    }
+
+    #[test]
+    fn empty_variables_fall_back_to_home() {
+        let vars = [
+            ("XDG_CONFIG_HOME", ""),
+            ("XDG_DATA_HOME", ""),
+            ("ZDOTDIR", ""),
+        ];
+        assert_eq!(
+            path_for(Shell::Fish, &vars),
+            Some(PathBuf::from("/home/me/.config/fish/completions/vpm.fish"))
+        );
+        assert_eq!(
+            path_for(Shell::Zsh, &vars),
+            Some(PathBuf::from("/home/me/.zfunc/_vpm"))
+        );
+        assert_eq!(
+            path_for(Shell::Bash, &vars),
+            Some(PathBuf::from(
+                "/home/me/.local/share/bash-completion/completions/vpm"
+            ))
+        );
+    }
+
+    #[test]
+    fn other_shells_have_no_completion_directory() {
+        assert_eq!(path_for(Shell::PowerShell, &[]), None);
+        assert_eq!(path_for(Shell::Elvish, &[]), None);
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fs, path::Path};
+
+/// Total size in bytes of the files under `path`, without following symlinks.
+pub fn dir_size(path: impl AsRef<Path>) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(&path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(&path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| dir_size(entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Number of files under `path`, without following symlinks.
+pub fn file_count(path: impl AsRef<Path>) -> usize {
+    let Ok(metadata) = fs::symlink_metadata(&path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return 1;
+    }
+    fs::read_dir(&path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| file_count(entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Format a byte count with a binary unit, e.g. `12.3 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
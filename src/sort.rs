@@ -0,0 +1,147 @@
+use clap::ValueEnum;
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+use crate::Project;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Sort {
+    #[default]
+    Id,
+    #[value(help = "Natural, case-insensitive name order (project-2 before project-10)")]
+    Name,
+    #[value(help = "Plain lexicographic name order")]
+    NameLexical,
+    #[clap(alias = "date")]
+    Created,
+    #[value(help = "Creation age, youngest first")]
+    Age,
+    Accessed,
+    #[value(help = "Days since last access, most recently used first")]
+    DaysSinceAccess,
+    Status,
+    #[value(help = "Filesystem path, grouping by status folder and then by full name")]
+    Path,
+}
+
+/// Compare two projects by each sort key in order, falling back to the id so ties are stable.
+pub fn compare(a: &Project, b: &Project, sort: &[Sort]) -> Ordering {
+    sort.iter()
+        .fold(Ordering::Equal, |ordering, sort_order| {
+            ordering.then_with(|| match sort_order {
+                Sort::Id => a.id.cmp(&b.id),
+                Sort::Name => natural_cmp(&a.name, &b.name),
+                Sort::NameLexical => a.name.cmp(&b.name),
+                Sort::Created => a.date.cmp(&b.date),
+                Sort::Age => a.age_days().cmp(&b.age_days()),
+                Sort::Accessed => a.last_accessed.cmp(&b.last_accessed),
+                Sort::DaysSinceAccess => a.days_since_access().cmp(&b.days_since_access()),
+                Sort::Status => a.status.cmp(&b.status),
+                // Same order as comparing `get_path()`, without building the paths per comparison.
+                Sort::Path => (a.status.to_string(), a.full_name())
+                    .cmp(&(b.status.to_string(), b.full_name())),
+            })
+        })
+        .then(a.id.cmp(&b.id))
+}
+
+/// Case-insensitive comparison that orders runs of digits by their numeric value.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let ordering = match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (take_number(&mut a_chars), take_number(&mut b_chars));
+                x.len().cmp(&y.len()).then(x.cmp(&y))
+            }
+            (Some(x), Some(y)) => {
+                a_chars.next();
+                b_chars.next();
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Consume a run of digits, returning it without leading zeros.
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, NaiveDate};
+
+    fn project(id: usize, name: &str) -> Project {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        Project::new("/projects", id, name, date, Local::now())
+    }
+
+    #[test]
+    fn natural_order() {
+        let cases = [
+            ("project-2", "project-10", Ordering::Less),
+            ("project-10", "project-2", Ordering::Greater),
+            ("Project-2", "project-10", Ordering::Less),
+            ("alpha", "Beta", Ordering::Less),
+            ("ALPHA", "alpha", Ordering::Less),
+            // Equal numbers fall back to plain order, so leading zeros sort first.
+            ("v007", "v7", Ordering::Less),
+            ("v007", "v8", Ordering::Less),
+            ("v0", "v00", Ordering::Less),
+            ("a1b2", "a1b10", Ordering::Less),
+            ("abc", "abcd", Ordering::Less),
+            ("same", "same", Ordering::Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(natural_cmp(a, b), expected, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn name_sorts_differ_on_numbers() {
+        let (two, ten) = (project(1, "project-2"), project(2, "project-10"));
+        assert_eq!(compare(&two, &ten, &[Sort::Name]), Ordering::Less);
+        assert_eq!(compare(&two, &ten, &[Sort::NameLexical]), Ordering::Greater);
+    }
+
+    #[test]
+    fn ties_fall_back_to_the_id() {
+        let (first, second) = (project(1, "same"), project(2, "same"));
+        for &sort in Sort::value_variants() {
+            assert_eq!(
+                compare(&first, &second, &[sort]),
+                Ordering::Less,
+                "{sort:?}"
+            );
+            assert_eq!(
+                compare(&second, &first, &[sort]),
+                Ordering::Greater,
+                "{sort:?}"
+            );
+        }
+        assert_eq!(compare(&first, &second, &[]), Ordering::Less);
+    }
+
+    #[test]
+    fn later_keys_break_earlier_ties() {
+        let (a, b) = (project(2, "alpha"), project(1, "beta"));
+        assert_eq!(compare(&a, &b, &[Sort::Status, Sort::Name]), Ordering::Less);
+        assert_eq!(
+            compare(&a, &b, &[Sort::Status, Sort::Id]),
+            Ordering::Greater
+        );
+    }
+}
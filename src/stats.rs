@@ -0,0 +1,251 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Bucket of the projects without tags in `--by-tag`.
+pub const UNTAGGED: &str = "(untagged)";
+/// Bucket of the projects whose kind could not be detected in `--by-kind`.
+pub const UNKNOWN_KIND: &str = "(unknown)";
+
+/// What `vpm stats` needs to know about one project.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub id: usize,
+    pub name: String,
+    pub status: String,
+    pub tags: Vec<String>,
+    pub kind: Option<String>,
+    pub created: NaiveDate,
+    pub size: u64,
+    pub last_accessed: DateTime<Local>,
+}
+
+/// How to split the projects into buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    Status,
+    Tag,
+    Kind,
+    Year,
+}
+
+impl Grouping {
+    /// The buckets `sample` falls into. A project with several tags counts towards each of them.
+    fn keys(self, sample: &Sample) -> Vec<String> {
+        match self {
+            Grouping::Status => vec![sample.status.clone()],
+            Grouping::Tag if sample.tags.is_empty() => vec![UNTAGGED.to_string()],
+            Grouping::Tag => sample.tags.clone(),
+            Grouping::Kind => vec![sample
+                .kind
+                .clone()
+                .unwrap_or_else(|| UNKNOWN_KIND.to_string())],
+            Grouping::Year => vec![sample.created.year().to_string()],
+        }
+    }
+}
+
+/// Project count, total size and most recent access of a group of projects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Aggregate {
+    pub projects: usize,
+    pub size: u64,
+    pub last_activity: Option<DateTime<Local>>,
+}
+
+impl Aggregate {
+    pub fn add(&mut self, sample: &Sample) {
+        self.projects += 1;
+        self.size += sample.size;
+        self.last_activity = self.last_activity.max(Some(sample.last_accessed));
+    }
+}
+
+/// The aggregate over every sample.
+pub fn total<'a>(samples: impl IntoIterator<Item = &'a Sample>) -> Aggregate {
+    let mut total = Aggregate::default();
+    for sample in samples {
+        total.add(sample);
+    }
+    total
+}
+
+/// The aggregate of each bucket of `by`, keyed by bucket name.
+pub fn buckets<'a>(
+    samples: impl IntoIterator<Item = &'a Sample>,
+    by: Grouping,
+) -> BTreeMap<String, Aggregate> {
+    let mut buckets = BTreeMap::<String, Aggregate>::new();
+    for sample in samples {
+        for key in by.keys(sample) {
+            buckets.entry(key).or_default().add(sample);
+        }
+    }
+    buckets
+}
+
+/// A project singled out by `highlights`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Highlight {
+    pub id: usize,
+    pub name: String,
+    pub date: DateTime<Local>,
+}
+
+/// The oldest and newest projects by creation date, and the one accessed most recently.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Highlights {
+    pub oldest: Option<Highlight>,
+    pub newest: Option<Highlight>,
+    pub last_accessed: Option<Highlight>,
+}
+
+/// Pick the highlights of `samples`, breaking ties on the lowest id.
+pub fn highlights(samples: &[Sample]) -> Highlights {
+    let created = |sample: &Sample| {
+        let midnight = sample.created.and_time(Default::default());
+        Highlight {
+            id: sample.id,
+            name: sample.name.clone(),
+            date: midnight
+                .and_local_timezone(Local)
+                .earliest()
+                .unwrap_or_default(),
+        }
+    };
+    Highlights {
+        oldest: samples
+            .iter()
+            .min_by_key(|sample| (sample.created, sample.id))
+            .map(created),
+        newest: samples
+            .iter()
+            .max_by_key(|sample| (sample.created, Reverse(sample.id)))
+            .map(created),
+        last_accessed: samples
+            .iter()
+            .max_by_key(|sample| (sample.last_accessed, Reverse(sample.id)))
+            .map(|sample| Highlight {
+                id: sample.id,
+                name: sample.name.clone(),
+                date: sample.last_accessed,
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, day, 12, 0, 0).unwrap()
+    }
+
+    fn sample(id: usize, year: i32, tags: &[&str], kind: Option<&str>) -> Sample {
+        Sample {
+            id,
+            name: format!("project-{id}"),
+            status: if id.is_multiple_of(2) {
+                "Paused"
+            } else {
+                "Active"
+            }
+            .to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            kind: kind.map(str::to_string),
+            created: NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+            size: 100 * id as u64,
+            last_accessed: at(id as u32),
+        }
+    }
+
+    fn samples() -> Vec<Sample> {
+        vec![
+            sample(1, 2022, &["cli", "rust"], Some("rust")),
+            sample(2, 2023, &["rust"], Some("rust")),
+            sample(3, 2023, &[], None),
+            sample(4, 2024, &["web"], Some("web")),
+        ]
+    }
+
+    fn aggregate(projects: usize, size: u64, last_day: u32) -> Aggregate {
+        Aggregate {
+            projects,
+            size,
+            last_activity: Some(at(last_day)),
+        }
+    }
+
+    #[test]
+    fn totals_every_sample() {
+        assert_eq!(total(&samples()), aggregate(4, 1000, 4));
+        assert_eq!(total(&[]), Aggregate::default());
+    }
+
+    #[test]
+    fn buckets_by_status_kind_and_year() {
+        let samples = samples();
+        assert_eq!(
+            buckets(&samples, Grouping::Status)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [
+                ("Active".to_string(), aggregate(2, 400, 3)),
+                ("Paused".to_string(), aggregate(2, 600, 4)),
+            ]
+        );
+        assert_eq!(
+            buckets(&samples, Grouping::Kind)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [
+                (UNKNOWN_KIND.to_string(), aggregate(1, 300, 3)),
+                ("rust".to_string(), aggregate(2, 300, 2)),
+                ("web".to_string(), aggregate(1, 400, 4)),
+            ]
+        );
+        assert_eq!(
+            buckets(&samples, Grouping::Year)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [
+                ("2022".to_string(), aggregate(1, 100, 1)),
+                ("2023".to_string(), aggregate(2, 500, 3)),
+                ("2024".to_string(), aggregate(1, 400, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn projects_count_towards_each_of_their_tags() {
+        let by_tag = buckets(&samples(), Grouping::Tag);
+        assert_eq!(
+            by_tag.clone().into_iter().collect::<Vec<_>>(),
+            [
+                (UNTAGGED.to_string(), aggregate(1, 300, 3)),
+                ("cli".to_string(), aggregate(1, 100, 1)),
+                ("rust".to_string(), aggregate(2, 300, 2)),
+                ("web".to_string(), aggregate(1, 400, 4)),
+            ]
+        );
+        let counted = by_tag.values().map(|bucket| bucket.projects).sum::<usize>();
+        assert_eq!(counted, 5);
+        assert!(buckets(&[], Grouping::Tag).is_empty());
+    }
+
+    #[test]
+    fn highlights_break_ties_on_the_lowest_id() {
+        let mut samples = samples();
+        samples.push(sample(5, 2022, &[], None));
+        samples[4].last_accessed = at(4);
+        let picked = highlights(&samples);
+        let ids = |highlight: &Option<Highlight>| highlight.as_ref().map(|h| h.id);
+        assert_eq!(ids(&picked.oldest), Some(1));
+        assert_eq!(ids(&picked.newest), Some(4));
+        assert_eq!(ids(&picked.last_accessed), Some(4));
+        assert_eq!(picked.last_accessed.unwrap().date, at(4));
+        assert_eq!(highlights(&[]), Highlights::default());
+    }
+}
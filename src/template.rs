@@ -0,0 +1,604 @@
+use chrono::{Local, NaiveDate};
+use color_eyre::eyre::{anyhow, Result};
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    atomic,
+    export::{self, ManifestFile},
+    fsops, meta, Project,
+};
+
+const TEMPLATES_DIR: &str = "templates";
+/// Holds the metadata of each template, so that it isn't copied into new projects.
+const META_DIR: &str = ".vpm";
+/// The `{{VARIABLE}}` placeholders vpm fills in when creating a project from a template.
+pub const KNOWN_VARIABLES: &[&str] = &[];
+/// Binary files above this size are flagged by `template validate`.
+const LARGE_BINARY_BYTES: u64 = 1024 * 1024;
+const DEFAULT_GITIGNORE: &str = ".DS_Store\n.env\n*.log\n";
+
+/// Metadata stored for a template in `templates/.vpm/<name>.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateMeta {
+    /// Where the template was imported from, if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<TemplateOrigin>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateOrigin {
+    /// File name of the archive the template was imported from.
+    pub file: String,
+    /// Name of the template in the archive.
+    pub name: String,
+    pub imported: NaiveDate,
+}
+
+/// Describes an exported template and the checksum of every file in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateManifest {
+    pub template: String,
+    #[serde(default)]
+    pub meta: TemplateMeta,
+    pub files: Vec<ManifestFile>,
+}
+
+pub fn templates_root(root: impl AsRef<Path>) -> PathBuf {
+    root.as_ref().join(TEMPLATES_DIR)
+}
+
+pub fn template_path(root: impl AsRef<Path>, name: &str) -> PathBuf {
+    templates_root(root).join(name)
+}
+
+/// Names of the templates in `root`, sorted. Only folders (or symlinks to them) count, and
+/// dotfiles are skipped.
+pub fn names(root: impl AsRef<Path>) -> Result<Vec<String>> {
+    let mut names = match fs::read_dir(templates_root(root)) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !name.starts_with('.'))
+            .collect::<Vec<_>>(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    names.sort();
+    Ok(names)
+}
+
+/// Entries of the templates folder that are neither templates nor dotfiles, sorted.
+pub fn stray_entries(root: impl AsRef<Path>) -> Result<Vec<String>> {
+    let mut strays = match fs::read_dir(templates_root(root)) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with('.'))
+            .collect::<Vec<_>>(),
+        Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    strays.sort();
+    Ok(strays)
+}
+
+/// Reverse index from template name to the ids of the projects created from it.
+pub fn users_by_template(
+    projects: &BTreeMap<usize, Project>,
+) -> Result<BTreeMap<String, Vec<usize>>> {
+    let mut users: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for project in projects.values() {
+        if let Some(template) = meta::load(project.get_path())?.and_then(|meta| meta.template) {
+            users.entry(template).or_default().push(project.id);
+        }
+    }
+    Ok(users)
+}
+
+pub fn users(projects: &BTreeMap<usize, Project>, name: &str) -> Result<Vec<usize>> {
+    Ok(users_by_template(projects)?
+        .remove(name)
+        .unwrap_or_default())
+}
+
+/// Point the provenance of the project at `project_path` to the template `name`.
+pub fn set_provenance(project_path: impl AsRef<Path>, name: &str) -> Result<()> {
+    let mut project_meta = meta::load(&project_path)?.unwrap_or_default();
+    project_meta.template = Some(name.to_string());
+    meta::save(project_path, &project_meta)
+}
+
+pub fn rename(root: impl AsRef<Path>, from: &str, to: &str) -> Result<()> {
+    let (old_path, new_path) = (template_path(&root, from), template_path(&root, to));
+    if !old_path.is_dir() {
+        return Err(anyhow!("Template {from} does not exist!"));
+    }
+    if new_path.exists() {
+        return Err(anyhow!("Template {to} already exists!"));
+    }
+    fs::rename(old_path, new_path)?;
+    if meta_path(&root, from).exists() {
+        fs::rename(meta_path(&root, from), meta_path(&root, to))?;
+    }
+    Ok(())
+}
+
+pub fn delete(root: impl AsRef<Path>, name: &str) -> Result<()> {
+    fs::remove_dir_all(template_path(&root, name))?;
+    match fs::remove_file(meta_path(&root, name)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+pub fn meta_path(root: impl AsRef<Path>, name: &str) -> PathBuf {
+    templates_root(root)
+        .join(META_DIR)
+        .join(format!("{name}.toml"))
+}
+
+pub fn load_meta(root: impl AsRef<Path>, name: &str) -> Result<TemplateMeta> {
+    match fs::read_to_string(meta_path(root, name)) {
+        Ok(contents) => Ok(toml::from_str(&contents)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(TemplateMeta::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save_meta(root: impl AsRef<Path>, name: &str, meta: &TemplateMeta) -> Result<()> {
+    atomic::write(meta_path(root, name), toml::to_string(meta)?)?;
+    Ok(())
+}
+
+/// Write the template `name` and its metadata to a tarball at `output`.
+pub fn export(root: impl AsRef<Path>, name: &str, output: &Path) -> Result<()> {
+    let path = template_path(&root, name);
+    if !path.is_dir() {
+        return Err(anyhow!("Template {name} does not exist!"));
+    }
+    if output.exists() {
+        return Err(anyhow!("{} already exists!", output.display()));
+    }
+    let manifest = TemplateManifest {
+        template: name.to_string(),
+        meta: load_meta(&root, name)?,
+        files: export::manifest_files(&path)?,
+    };
+    export::write_tarball(output, &toml::to_string(&manifest)?, name, &path)
+}
+
+/// Unpack the template archive at `archive` into the templates directory and return its name.
+/// The template keeps its exported name unless `name` is given. An existing template with that
+/// name is replaced with `force`, otherwise the new one gets a numbered suffix.
+pub fn import(
+    root: impl AsRef<Path>,
+    archive: &Path,
+    name: Option<&str>,
+    force: bool,
+) -> Result<String> {
+    let staging = templates_root(&root)
+        .join(META_DIR)
+        .join(format!("import.{}", std::process::id()));
+    fs::create_dir_all(&staging)?;
+    let imported = import_from(&root, archive, &staging, name, force);
+    let _ = fs::remove_dir_all(&staging);
+    imported
+}
+
+fn import_from(
+    root: impl AsRef<Path>,
+    archive: &Path,
+    staging: &Path,
+    name: Option<&str>,
+    force: bool,
+) -> Result<String> {
+    let manifest: TemplateManifest = toml::from_str(&export::unpack_tarball(archive, staging)?)
+        .map_err(|err| anyhow!("{} is not a template archive: {err}", archive.display()))?;
+    let unpacked = staging.join(&manifest.template);
+    if !valid_name(&manifest.template) || !unpacked.is_dir() {
+        return Err(anyhow!(
+            "{} does not contain the template {}",
+            archive.display(),
+            manifest.template
+        ));
+    }
+    export::verify(&unpacked, &manifest.files)?;
+
+    let wanted = name.unwrap_or(&manifest.template);
+    if !valid_name(wanted) {
+        return Err(anyhow!("Invalid template name: {wanted}"));
+    }
+    let mut name = wanted.to_string();
+    if template_path(&root, &name).exists() {
+        if force {
+            delete(&root, &name)?;
+        } else {
+            name = (2..)
+                .map(|n| format!("{wanted}-{n}"))
+                .find(|name| !template_path(&root, name).exists())
+                .expect("ran out of template names");
+        }
+    }
+    fs::rename(&unpacked, template_path(&root, &name))?;
+
+    let mut meta = manifest.meta;
+    meta.origin = Some(TemplateOrigin {
+        file: archive
+            .file_name()
+            .map(|file| file.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        name: manifest.template,
+        imported: Local::now().date_naive(),
+    });
+    save_meta(&root, &name, &meta)?;
+    Ok(name)
+}
+
+/// Whether `name` can be used as a single directory name in the templates directory.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+/// Copy the project at `project_path` into a new template called `name`.
+/// Refuses layouts where the copy would end up inside its own source, and never follows symlinks.
+pub fn capture(root: impl AsRef<Path>, project_path: impl AsRef<Path>, name: &str) -> Result<()> {
+    let templates_root = templates_root(&root);
+    fs::create_dir_all(&templates_root)?;
+    let source = fs::canonicalize(&project_path)?;
+    let destination = fs::canonicalize(&templates_root)?.join(name);
+    if destination.exists() {
+        return Err(anyhow!("Template {name} already exists!"));
+    }
+    if destination.starts_with(&source) {
+        return Err(anyhow!(
+            "Refusing to capture {} into {}, which is inside the project",
+            source.display(),
+            destination.display()
+        ));
+    }
+    if source.starts_with(&destination) {
+        return Err(anyhow!(
+            "Refusing to capture {}, it is inside the template directory {}",
+            source.display(),
+            destination.display()
+        ));
+    }
+    fsops::copy_dir(&source, &destination)
+}
+
+/// Something `template validate` warns about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    UnknownVariable { path: PathBuf, variable: String },
+    LargeBinary { path: PathBuf, size: u64 },
+    SymlinkOutside { path: PathBuf, target: PathBuf },
+    MissingGitignore,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::UnknownVariable { path, variable } => write!(
+                f,
+                "{}: {{{{{variable}}}}} is not a variable vpm fills in",
+                path.display()
+            ),
+            Issue::LargeBinary { path, size } => write!(
+                f,
+                "{}: binary file of {}",
+                path.display(),
+                crate::size::format_size(*size)
+            ),
+            Issue::SymlinkOutside { path, target } => write!(
+                f,
+                "{}: symlink to {} points outside the template",
+                path.display(),
+                target.display()
+            ),
+            Issue::MissingGitignore => write!(f, "no .gitignore"),
+        }
+    }
+}
+
+/// Check the template `name` for unknown variables, large binaries, symlinks leaving the
+/// template and a missing `.gitignore`. Paths in the issues are relative to the template.
+pub fn validate(root: impl AsRef<Path>, name: &str) -> Result<Vec<Issue>> {
+    let path = template_path(root, name);
+    if !path.is_dir() {
+        return Err(anyhow!("Template {name} does not exist!"));
+    }
+    let variable = variable_regex()?;
+    let mut issues = Vec::new();
+    validate_dir(&path, Path::new(""), &variable, &mut issues)?;
+    if !path.join(".gitignore").exists() {
+        issues.push(Issue::MissingGitignore);
+    }
+    Ok(issues)
+}
+
+fn validate_dir(root: &Path, dir: &Path, variable: &Regex, issues: &mut Vec<Issue>) -> Result<()> {
+    for entry in fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            let target = fs::read_link(entry.path())?;
+            if !stays_inside(dir, &target) {
+                issues.push(Issue::SymlinkOutside { path, target });
+            }
+        } else if file_type.is_dir() {
+            validate_dir(root, &path, variable, issues)?;
+        } else if file_type.is_file() {
+            let size = entry.metadata()?.len();
+            let contents = fs::read(entry.path())?;
+            if is_binary(&contents) {
+                if size > LARGE_BINARY_BYTES {
+                    issues.push(Issue::LargeBinary { path, size });
+                }
+                continue;
+            }
+            let mut unknown = variables_in(&String::from_utf8_lossy(&contents), variable)
+                .filter(|name| !is_known(name))
+                .collect::<Vec<_>>();
+            unknown.sort();
+            unknown.dedup();
+            issues.extend(unknown.into_iter().map(|variable| Issue::UnknownVariable {
+                path: path.clone(),
+                variable,
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// Matches a `{{VARIABLE}}` placeholder, capturing the variable name.
+fn variable_regex() -> Result<Regex> {
+    Ok(Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}")?)
+}
+
+fn variables_in<'a>(text: &'a str, variable: &'a Regex) -> impl Iterator<Item = String> + 'a {
+    variable
+        .captures_iter(text)
+        .map(|captures| captures[1].to_string())
+}
+
+pub fn is_known(variable: &str) -> bool {
+    KNOWN_VARIABLES.contains(&variable)
+}
+
+/// Files with a NUL byte are treated as binary and never searched for placeholders.
+pub fn is_binary(contents: &[u8]) -> bool {
+    contents.contains(&0)
+}
+
+/// Where a template uses a variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableUse {
+    pub count: usize,
+    /// The first file, relative to the template, that uses the variable.
+    pub sample: PathBuf,
+}
+
+/// Every variable used in the contents or file names of the template `name`.
+pub fn variables(root: impl AsRef<Path>, name: &str) -> Result<BTreeMap<String, VariableUse>> {
+    let path = template_path(root, name);
+    if !path.is_dir() {
+        return Err(anyhow!("Template {name} does not exist!"));
+    }
+    let mut uses = BTreeMap::new();
+    collect_variables(&path, Path::new(""), &variable_regex()?, &mut uses)?;
+    Ok(uses)
+}
+
+fn collect_variables(
+    root: &Path,
+    dir: &Path,
+    variable: &Regex,
+    uses: &mut BTreeMap<String, VariableUse>,
+) -> Result<()> {
+    let mut entries = fs::read_dir(root.join(dir))?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = dir.join(entry.file_name());
+        let mut found = variables_in(&entry.file_name().to_string_lossy(), variable).collect_vec();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_variables(root, &path, variable, uses)?;
+        } else if file_type.is_file() {
+            let contents = fs::read(entry.path())?;
+            if !is_binary(&contents) {
+                found.extend(variables_in(&String::from_utf8_lossy(&contents), variable));
+            }
+        }
+        for name in found {
+            uses.entry(name)
+                .or_insert_with(|| VariableUse {
+                    count: 0,
+                    sample: path.clone(),
+                })
+                .count += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a symlink in `dir`, relative to the template, pointing at `target` stays inside it.
+fn stays_inside(dir: &Path, target: &Path) -> bool {
+    let mut depth = dir.components().count();
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+/// Fix what can be fixed automatically and return the issues that are left.
+pub fn fix(root: impl AsRef<Path>, name: &str, issues: Vec<Issue>) -> Result<Vec<Issue>> {
+    let mut left = Vec::new();
+    for issue in issues {
+        match issue {
+            Issue::MissingGitignore => {
+                fs::write(
+                    template_path(&root, name).join(".gitignore"),
+                    DEFAULT_GITIGNORE,
+                )?;
+                println!("Added a .gitignore");
+            }
+            issue => left.push(issue),
+        }
+    }
+    Ok(left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{tree, TempDir};
+
+    #[test]
+    fn captures_a_nested_project() {
+        let dir = TempDir::new();
+        let project = dir.mkdir("Active/p01-app-2024-01-01");
+        dir.write("Active/p01-app-2024-01-01/src/lib/mod.rs", "");
+        dir.write("Active/p01-app-2024-01-01/Cargo.toml", "");
+        capture(dir.path(), &project, "app").unwrap();
+        assert_eq!(tree(&template_path(dir.path(), "app")), tree(&project));
+        assert_eq!(names(dir.path()).unwrap(), ["app"]);
+    }
+
+    #[test]
+    fn refuses_to_capture_into_the_project_itself() {
+        let dir = TempDir::new();
+        dir.write("Cargo.toml", "");
+        let err = capture(dir.path(), dir.path(), "loop").unwrap_err();
+        assert!(err.to_string().contains("inside the project"), "{err}");
+        assert!(!template_path(dir.path(), "loop").exists());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_a_template() {
+        let dir = TempDir::new();
+        let project = dir.mkdir("Active/p01-app-2024-01-01");
+        dir.write("templates/app/keep", "");
+        let err = capture(dir.path(), &project, "app").unwrap_err();
+        assert_eq!(err.to_string(), "Template app already exists!");
+        assert_eq!(tree(&template_path(dir.path(), "app")), ["keep"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn captures_symlink_loops_as_links() {
+        let dir = TempDir::new();
+        let project = dir.mkdir("Active/p01-app-2024-01-01");
+        std::os::unix::fs::symlink("..", project.join("up")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), project.join("root")).unwrap();
+        capture(dir.path(), &project, "app").unwrap();
+        assert_eq!(tree(&template_path(dir.path(), "app")), ["root", "up"]);
+    }
+
+    fn uses(count: usize, sample: &str) -> VariableUse {
+        VariableUse {
+            count,
+            sample: PathBuf::from(sample),
+        }
+    }
+
+    #[test]
+    fn finds_variables_in_nested_contents_and_names() {
+        let dir = TempDir::new();
+        dir.write(
+            "templates/svc/README.md",
+            "# {{name}}\n{{ name }} by {{author}}\n",
+        );
+        dir.write(
+            "templates/svc/src/{{name}}/main.rs",
+            "// {{name}}\n{{projct_name}}\n",
+        );
+        dir.write("templates/svc/src/deeper/still/mod.rs", "{{author}}");
+        let assets = dir.mkdir("templates/svc/assets");
+        fs::write(assets.join("logo.bin"), b"\0{{binary}}").unwrap();
+        fs::write(assets.join("{{icon}}.png"), b"\0").unwrap();
+
+        let found = variables(dir.path(), "svc").unwrap();
+        assert_eq!(
+            found.into_iter().collect_vec(),
+            [
+                ("author".to_string(), uses(2, "README.md")),
+                ("icon".to_string(), uses(1, "assets/{{icon}}.png")),
+                ("name".to_string(), uses(4, "README.md")),
+                ("projct_name".to_string(), uses(1, "src/{{name}}/main.rs")),
+            ]
+        );
+        assert!(!is_known("projct_name"));
+    }
+
+    #[test]
+    fn a_template_without_variables_has_none() {
+        let dir = TempDir::new();
+        dir.write("templates/plain/a/b/c.txt", "{ not {{ a variable }} }");
+        dir.mkdir("templates/plain/empty");
+        assert!(variables(dir.path(), "plain").unwrap().is_empty());
+        let err = variables(dir.path(), "missing").unwrap_err();
+        assert_eq!(err.to_string(), "Template missing does not exist!");
+    }
+
+    /// A templates folder with two templates and the junk that collects next to them.
+    fn junk_templates() -> TempDir {
+        let dir = TempDir::new();
+        dir.write("templates/rust/Cargo.toml", "");
+        dir.mkdir("templates/web");
+        dir.write("templates/README.md", "");
+        dir.write("templates/.DS_Store", "");
+        dir.mkdir("templates/.vpm");
+        dir
+    }
+
+    #[test]
+    fn only_folders_are_templates() {
+        let dir = junk_templates();
+        assert_eq!(names(dir.path()).unwrap(), ["rust", "web"]);
+        assert_eq!(stray_entries(dir.path()).unwrap(), ["README.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_count_by_their_target() {
+        use std::os::unix::fs::symlink;
+        let dir = junk_templates();
+        let elsewhere = dir.mkdir("shared/go");
+        symlink(&elsewhere, dir.path().join("templates/go")).unwrap();
+        symlink(
+            dir.path().join("templates/README.md"),
+            dir.path().join("templates/docs"),
+        )
+        .unwrap();
+        symlink(dir.path().join("gone"), dir.path().join("templates/broken")).unwrap();
+        assert_eq!(names(dir.path()).unwrap(), ["go", "rust", "web"]);
+        assert_eq!(
+            stray_entries(dir.path()).unwrap(),
+            ["README.md", "broken", "docs"]
+        );
+    }
+
+    #[test]
+    fn a_missing_templates_folder_has_nothing() {
+        let dir = TempDir::new();
+        assert!(names(dir.path()).unwrap().is_empty());
+        assert!(stray_entries(dir.path()).unwrap().is_empty());
+    }
+}
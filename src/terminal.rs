@@ -0,0 +1,24 @@
+use color_eyre::eyre::{anyhow, Result};
+use std::{env, process::Command};
+
+/// Open `path` in a new tmux window named `name`, or split the current pane when `split` is set.
+pub fn open_in_tmux(path: &str, name: &str, split: bool) -> Result<()> {
+    if env::var_os("TMUX").is_none() {
+        return Err(anyhow!("Not inside a tmux session, $TMUX is not set!"));
+    }
+    let mut command = Command::new("tmux");
+    if split {
+        let pane = env::var("TMUX_PANE")
+            .map_err(|_| anyhow!("Not inside a tmux pane, $TMUX_PANE is not set!"))?;
+        command.args(["split-window", "-t", &pane, "-c", path]);
+    } else {
+        command.args(["new-window", "-c", path, "-n", name]);
+    }
+    let status = command
+        .status()
+        .map_err(|err| anyhow!("Failed to run tmux: {err}"))?;
+    if !status.success() {
+        return Err(anyhow!("tmux exited with {status}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDate;
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{atomic, paths::Paths};
+
+/// Record of a project that was archived outside of PROJECT_HOME.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub id: usize,
+    pub full_name: String,
+    pub destination: PathBuf,
+    pub date: NaiveDate,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Tombstones {
+    #[serde(default)]
+    tombstones: Vec<Tombstone>,
+}
+
+pub fn load(paths: &Paths) -> Result<Vec<Tombstone>> {
+    Ok(atomic::read_toml::<Tombstones>(paths.tombstones_file())?.tombstones)
+}
+
+pub fn find(paths: &Paths, id: usize) -> Result<Option<Tombstone>> {
+    Ok(load(paths)?
+        .into_iter()
+        .rev()
+        .find(|tombstone| tombstone.id == id))
+}
+
+pub fn record(paths: &Paths, tombstone: Tombstone) -> Result<()> {
+    atomic::update(
+        &paths.lock_file(),
+        paths.tombstones_file(),
+        |tombstones: &mut Tombstones| tombstones.tombstones.push(tombstone),
+    )
+}
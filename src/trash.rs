@@ -0,0 +1,158 @@
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use color_eyre::eyre::{anyhow, Result};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{fsops, when::Span};
+
+const TRASH_DIR: &str = ".trash";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+
+pub fn trash_dir(root: impl AsRef<Path>) -> PathBuf {
+    root.as_ref().join(TRASH_DIR)
+}
+
+/// Move `path` into the trash of `root`, suffixing its name with the current time.
+pub fn trash(root: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .ok_or(anyhow!("Cannot trash {}", path.display()))?
+        .to_string_lossy();
+    let dir = trash_dir(root);
+    fs::create_dir_all(&dir)?;
+    let trashed = dir.join(format!("{name}.{}", Local::now().format(TIMESTAMP_FORMAT)));
+    // The status folders may be symlinks to other file systems.
+    fsops::move_dir(path, &trashed)?;
+    Ok(trashed)
+}
+
+/// A project in the trash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trashed {
+    pub path: PathBuf,
+    /// The name the project had before it was trashed.
+    pub name: String,
+    pub trashed_at: NaiveDateTime,
+}
+
+/// Split a trashed folder name into the original name and the time it was trashed.
+pub fn parse_trashed_name(file_name: &str) -> Option<(String, NaiveDateTime)> {
+    let (name, timestamp) = file_name.rsplit_once('.')?;
+    let trashed_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    Some((name.to_string(), trashed_at))
+}
+
+/// Everything in the trash of `root`, oldest first. Entries without a timestamp are ignored.
+pub fn list(root: impl AsRef<Path>) -> Result<Vec<Trashed>> {
+    let entries = match fs::read_dir(trash_dir(root)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut trashed = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let (name, trashed_at) = parse_trashed_name(&entry.file_name().to_string_lossy())?;
+            Some(Trashed {
+                path: entry.path(),
+                name,
+                trashed_at,
+            })
+        })
+        .collect::<Vec<_>>();
+    trashed.sort_by_key(|item| item.trashed_at);
+    Ok(trashed)
+}
+
+/// The items of `trashed` that were trashed longer than `retention` before `today`.
+pub fn expired(trashed: Vec<Trashed>, retention: Span, today: NaiveDate) -> Vec<Trashed> {
+    let cutoff = retention.before(today);
+    trashed
+        .into_iter()
+        .filter(|item| item.trashed_at.date() < cutoff)
+        .collect()
+}
+
+/// Permanently delete `items`.
+pub fn empty(items: &[Trashed]) -> Result<()> {
+    for item in items {
+        let removed = if item.path.is_dir() {
+            fs::remove_dir_all(&item.path)
+        } else {
+            fs::remove_file(&item.path)
+        };
+        removed.map_err(|err| anyhow!("Failed to delete {}: {err}", item.path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).unwrap()
+    }
+
+    fn trashed(name: &str, timestamp: &str) -> Trashed {
+        Trashed {
+            path: PathBuf::from(format!("{name}.{timestamp}")),
+            name: name.to_string(),
+            trashed_at: at(timestamp),
+        }
+    }
+
+    #[test]
+    fn trashed_names_split_at_the_last_dot() {
+        assert_eq!(
+            parse_trashed_name("p01-app-2024-01-01.2024-03-01T12-30-05"),
+            Some(("p01-app-2024-01-01".to_string(), at("2024-03-01T12-30-05")))
+        );
+        assert_eq!(
+            parse_trashed_name("notes.v2.2024-03-01T00-00-00"),
+            Some(("notes.v2".to_string(), at("2024-03-01T00-00-00")))
+        );
+    }
+
+    #[test]
+    fn malformed_trashed_names_are_ignored() {
+        for name in [
+            "",
+            "p01-app-2024-01-01",
+            "p01-app.",
+            "p01-app.2024-03-01",
+            "p01-app.2024-13-01T00-00-00",
+            "p01-app.2024-03-01 00:00:00",
+            "p01-app.2024-03-01T00-00-00.bak",
+        ] {
+            assert_eq!(parse_trashed_name(name), None, "{name:?}");
+        }
+    }
+
+    #[test]
+    fn items_expire_after_the_whole_retention_day() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let items = vec![
+            trashed("old", "2024-02-29T23-59-59"),
+            trashed("boundary", "2024-03-01T00-00-00"),
+            trashed("recent", "2024-03-30T12-00-00"),
+        ];
+        let expired = expired(items, "30d".parse().unwrap(), today);
+        assert_eq!(expired, [trashed("old", "2024-02-29T23-59-59")]);
+    }
+
+    #[test]
+    fn month_retention_clamps_to_shorter_months() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let items = vec![
+            trashed("old", "2024-02-28T23-59-59"),
+            trashed("boundary", "2024-02-29T00-00-00"),
+        ];
+        let expired = expired(items, "1m".parse().unwrap(), today);
+        assert_eq!(expired, [trashed("old", "2024-02-28T23-59-59")]);
+    }
+}
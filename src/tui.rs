@@ -1,28 +1,60 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     error::Error,
+    fs,
     io::{self, Stdout},
     time::Duration,
 };
 
 use crossterm::{
+    cursor::Show,
     event::{self, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
+use chrono::Local;
+use itertools::Itertools;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
 };
 
-use crate::Project;
+use crate::{
+    editor,
+    fsops::ScratchDir,
+    human_age, layout, meta, peek,
+    scheduler::Scheduler,
+    size,
+    sort::{self, Sort},
+    Project,
+};
+
+/// Columns taken by the list borders and the highlight symbol.
+const LIST_CHROME_WIDTH: usize = 4;
+/// How long the selection has to rest on a project before it is probed.
+const PROBE_DEBOUNCE: Duration = Duration::from_millis(150);
+const PROBE_POLL: Duration = Duration::from_millis(50);
+const SIZE_WORKERS: usize = 2;
+const PREVIEW_WORKERS: usize = 1;
+/// Height of the description pane, borders included.
+const DETAIL_HEIGHT: u16 = 4;
 
-pub fn start(projects: BTreeMap<usize, Project>) -> Result<(), Box<dyn Error>> {
+/// Puts the terminal back into cooked mode on the main screen when dropped, so an error or
+/// panic inside the TUI doesn't leave the shell unusable.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+    }
+}
+
+pub fn start(projects: BTreeMap<usize, Project>, sort: Sort) -> Result<(), Box<dyn Error>> {
+    let _guard = TerminalGuard;
     let mut terminal = setup_terminal()?;
-    run(&mut terminal, projects)?;
-    restore_terminal(&mut terminal)?;
-    Ok(())
+    run(&mut terminal, projects, sort)
 }
 
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, Box<dyn Error>> {
@@ -35,54 +67,242 @@ fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, Box<dyn Error>
 fn run(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     projects: BTreeMap<usize, Project>,
+    sort: Sort,
 ) -> Result<(), Box<dyn Error>> {
     let mut selected_project = 0usize;
+    let rows = projects
+        .values()
+        .sorted_by(|a, b| {
+            let ordering = sort::compare(a, b, &[sort]);
+            // Recency reads best with the most recently accessed project on top.
+            if sort == Sort::Accessed {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+        .collect::<Vec<_>>();
+    let (mut show_created, mut show_touched) = (false, false);
+    let mut descriptions = rows
+        .iter()
+        .map(|p| {
+            let description = meta::load(p.get_path())
+                .ok()
+                .flatten()
+                .map(|meta| meta.description)
+                .unwrap_or_default();
+            (p.id, description)
+        })
+        .collect::<HashMap<_, _>>();
+    // The description being edited inline, if any.
+    let mut editing: Option<String> = None;
+    // The outcome of the last failed save or edit, shown in place of the description.
+    let mut notice: Option<String> = None;
+    let mut sizes = Scheduler::new(SIZE_WORKERS, PROBE_DEBOUNCE, |path: &String| {
+        size::dir_size(path)
+    });
+    let mut previews = Scheduler::new(PREVIEW_WORKERS, PROBE_DEBOUNCE, |path: &String| {
+        peek::preview(path)
+    });
     loop {
+        let selected = rows.get(selected_project);
+        let selected_path = selected.map(|p| p.get_path());
+        if let Some(path) = &selected_path {
+            sizes.request(path.clone());
+            previews.request(path.clone());
+        }
+        sizes.tick();
+        previews.tick();
+        let preview = selected_path
+            .as_ref()
+            .and_then(|path| previews.get(path))
+            .map(|lines| lines.join("\n"))
+            .unwrap_or_default();
+        let title = match selected_path.as_ref().map(|path| sizes.get(path)) {
+            Some(Some(&size)) => format!("Projects ─ {}", size::format_size(size)),
+            Some(None) => "Projects ─ …".to_string(),
+            None => "Projects".to_string(),
+        };
+        let description = selected
+            .and_then(|p| descriptions.get(&p.id))
+            .cloned()
+            .unwrap_or_default();
         terminal.draw(|frame| {
-            // let greeting = Paragraph::new("Hello World!");
-            let items = projects
-                .values()
+            let [main_area, detail_area] = *Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(DETAIL_HEIGHT)])
+                .split(frame.size())
+            else {
+                return;
+            };
+            let [list_area, preview_area] = *Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(main_area)
+            else {
+                return;
+            };
+            let total_width = (list_area.width as usize).saturating_sub(LIST_CHROME_WIDTH);
+            let id_width = rows
+                .iter()
+                .map(|p| p.id.to_string().len())
+                .max()
+                .unwrap_or(0)
+                .max(2);
+            let items = rows
+                .iter()
                 .map(|p| {
-                    ListItem::new(format!(
-                        "{:02} | {} | {}",
+                    let mut prefix = format!(
+                        "{:0id_width$} | {} | {:8} | ",
                         p.id,
                         p.date,
-                        p.name.split('-').collect::<Vec<_>>().join(" ")
-                    ))
+                        p.kind.as_deref().unwrap_or("-")
+                    );
+                    if show_created {
+                        prefix.push_str(&format!("{:>10} | ", human_age(p.age_days())));
+                    }
+                    if show_touched {
+                        let touched = (Local::now() - p.last_accessed).num_days();
+                        prefix.push_str(&format!("{:>10} | ", human_age(touched)));
+                    }
+                    let name = p.name.split('-').collect::<Vec<_>>().join(" ");
+                    let width = layout::name_width(prefix.chars().count(), total_width);
+                    ListItem::new(format!("{prefix}{}", layout::truncate(&name, width)))
                 })
                 .collect::<Vec<_>>();
             let list = List::new(items)
-                .block(Block::default().title("Projects").borders(Borders::ALL))
+                .block(
+                    Block::default()
+                        .title(format!("{title} (c: created, t: touched)"))
+                        .borders(Borders::ALL),
+                )
                 .style(Style::default().fg(Color::White))
                 .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
                 .highlight_symbol(">>");
             let mut list_state = ListState::default();
             list_state.select(Some(selected_project));
-            frame.render_stateful_widget(list, frame.size(), &mut list_state);
+            frame.render_stateful_widget(list, list_area, &mut list_state);
+            let preview = Paragraph::new(preview.as_str())
+                .block(Block::default().title("Preview").borders(Borders::ALL));
+            frame.render_widget(preview, preview_area);
+
+            let (detail_title, text) = match (&editing, &notice) {
+                (Some(input), _) => ("Description (enter: save, esc: cancel)", input.as_str()),
+                (None, Some(notice)) => ("Description (any key: dismiss)", notice.as_str()),
+                (None, None) => (
+                    "Description (e: edit, E: edit in $EDITOR)",
+                    description.as_str(),
+                ),
+            };
+            let detail_style = match (&editing, &notice) {
+                (None, Some(_)) => Style::default().fg(Color::Red),
+                _ => Style::default(),
+            };
+            let detail = Paragraph::new(text)
+                .style(detail_style)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().title(detail_title).borders(Borders::ALL));
+            frame.render_widget(detail, detail_area);
+            if let Some(input) = &editing {
+                let inner_width = detail_area.width.saturating_sub(2).max(1);
+                let len = input.chars().count() as u16;
+                frame.set_cursor(
+                    detail_area.x + 1 + len % inner_width,
+                    (detail_area.y + 1 + len / inner_width)
+                        .min(detail_area.bottom().saturating_sub(2)),
+                );
+            }
         })?;
-        if event::poll(Duration::from_millis(1000))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            if selected_project == 0 {
-                                selected_project = projects.len();
+        let timeout = if sizes.is_busy() || previews.is_busy() {
+            PROBE_POLL
+        } else {
+            Duration::from_millis(1000)
+        };
+        if !event::poll(timeout)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if notice.take().is_some() {
+            continue;
+        }
+        if let Some(input) = editing.as_mut() {
+            match key.code {
+                KeyCode::Enter => {
+                    if let (Some(project), Some(input)) = (selected, editing.take()) {
+                        match meta::set_description(project.get_path(), &input) {
+                            Ok(()) => {
+                                descriptions.insert(project.id, input);
                             }
-                            selected_project -= 1;
+                            Err(err) => notice = Some(format!("Could not save: {err}")),
                         }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            selected_project = (selected_project + 1) % projects.len();
+                    }
+                }
+                KeyCode::Esc => editing = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') => break,
+            KeyCode::Char('c') => show_created = !show_created,
+            KeyCode::Char('t') => show_touched = !show_touched,
+            KeyCode::Char('e') if selected.is_some() => editing = Some(description),
+            KeyCode::Char('E') => {
+                if let Some(project) = selected {
+                    let saved = edit_externally(terminal, &description).and_then(|edited| {
+                        meta::set_description(project.get_path(), &edited)
+                            .map_err(|err| err.to_string())?;
+                        Ok(edited)
+                    });
+                    match saved {
+                        Ok(edited) => {
+                            descriptions.insert(project.id, edited);
                         }
-                        _ => {}
+                        Err(err) => notice = Some(format!("Could not edit: {err}")),
                     }
                 }
             }
+            KeyCode::Up | KeyCode::Char('k') if !projects.is_empty() => {
+                if selected_project == 0 {
+                    selected_project = projects.len();
+                }
+                selected_project -= 1;
+            }
+            KeyCode::Down | KeyCode::Char('j') if !projects.is_empty() => {
+                selected_project = (selected_project + 1) % projects.len();
+            }
+            _ => {}
         }
     }
     Ok(())
 }
 
+/// Suspend the TUI to edit `text` in `$EDITOR`, then bring the TUI back.
+fn edit_externally(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    text: &str,
+) -> Result<String, Box<dyn Error>> {
+    let scratch = ScratchDir::new("vpm-description")?;
+    let buffer_path = scratch.create_file("description.txt", text)?;
+    restore_terminal(terminal)?;
+    let edited = editor::edit_file(&buffer_path)
+        .map_err(|err| err.to_string())
+        .and_then(|()| fs::read_to_string(&buffer_path).map_err(|err| err.to_string()));
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(edited?.trim_end().to_string())
+}
+
 fn restore_terminal(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
 ) -> Result<(), Box<dyn Error>> {
@@ -91,3 +311,72 @@ fn restore_terminal(
     terminal.show_cursor()?;
     Ok(())
 }
+
+/// Let the user pick any number of `items`, returning their indices, or `None` if cancelled.
+pub fn multi_select(title: &str, items: &[String]) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+    let _guard = TerminalGuard;
+    let mut terminal = setup_terminal()?;
+    run_multi_select(&mut terminal, title, items)
+}
+
+fn run_multi_select(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    title: &str,
+    items: &[String],
+) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+    let mut selected = vec![false; items.len()];
+    let mut cursor = 0usize;
+    loop {
+        terminal.draw(|frame| {
+            let list_items = items
+                .iter()
+                .zip(selected.iter())
+                .map(|(item, &checked)| {
+                    ListItem::new(format!("[{}] {}", if checked { "x" } else { " " }, item))
+                })
+                .collect::<Vec<_>>();
+            let list = List::new(list_items)
+                .block(
+                    Block::default()
+                        .title(format!(
+                            "{title} (space: toggle, a: all, enter: confirm, q: cancel)"
+                        ))
+                        .borders(Borders::ALL),
+                )
+                .style(Style::default().fg(Color::White))
+                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+                .highlight_symbol(">>");
+            let mut list_state = ListState::default();
+            list_state.select(Some(cursor));
+            frame.render_stateful_widget(list, frame.size(), &mut list_state);
+        })?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press || items.is_empty() {
+                if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(None);
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                KeyCode::Enter => {
+                    return Ok(Some(
+                        selected.iter().positions(|&checked| checked).collect(),
+                    ))
+                }
+                KeyCode::Char(' ') if !items.is_empty() => selected[cursor] = !selected[cursor],
+                KeyCode::Char('a') => {
+                    let all = selected.iter().all(|&checked| checked);
+                    selected.iter_mut().for_each(|checked| *checked = !all);
+                }
+                KeyCode::Up | KeyCode::Char('k') if !items.is_empty() => {
+                    cursor = cursor.checked_sub(1).unwrap_or(items.len() - 1);
+                }
+                KeyCode::Down | KeyCode::Char('j') if !items.is_empty() => {
+                    cursor = (cursor + 1) % items.len()
+                }
+                _ => {}
+            }
+        }
+    }
+}
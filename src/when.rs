@@ -0,0 +1,240 @@
+use chrono::{Days, Duration, Months, NaiveDate};
+use std::{fmt::Display, str::FromStr};
+
+const ACCEPTED: &str =
+    "expected a date (2024-05-01, 2024-05 or 2024), a duration (90d, 2w, 6m or 1y), `today` or `yesterday`";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// A calendar-aware length of time, e.g. `6m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub amount: u32,
+    pub unit: Unit,
+}
+
+impl Span {
+    /// The date `self` before `date`, clamping to the end of shorter months.
+    pub fn before(&self, date: NaiveDate) -> NaiveDate {
+        let months = |n: u32| date.checked_sub_months(Months::new(n));
+        match self.unit {
+            Unit::Day => date.checked_sub_days(Days::new(self.amount.into())),
+            Unit::Week => date.checked_sub_days(Days::new(7 * u64::from(self.amount))),
+            Unit::Month => months(self.amount),
+            Unit::Year => months(self.amount.saturating_mul(12)),
+        }
+        .unwrap_or(NaiveDate::MIN)
+    }
+}
+
+impl FromStr for Span {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        let split = input
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| format!("`{input}` is missing a unit, expected one of d, w, m or y"))?;
+        let (amount, unit) = input.split_at(split);
+        let amount = amount
+            .parse()
+            .map_err(|_| format!("`{input}` does not start with a number"))?;
+        let unit = match unit {
+            "d" | "day" | "days" => Unit::Day,
+            "w" | "week" | "weeks" => Unit::Week,
+            "m" | "month" | "months" => Unit::Month,
+            "y" | "year" | "years" => Unit::Year,
+            _ => {
+                return Err(format!(
+                    "unknown unit `{unit}`, expected one of d, w, m or y"
+                ))
+            }
+        };
+        Ok(Self { amount, unit })
+    }
+}
+
+/// A point in time given on the command line, either a (partial) date or a span before today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Day(NaiveDate),
+    Month { year: i32, month: u32 },
+    Year(i32),
+    Today,
+    Yesterday,
+    Ago(Span),
+}
+
+impl When {
+    /// The first day covered by `self`.
+    pub fn first_day(&self, today: NaiveDate) -> NaiveDate {
+        match *self {
+            When::Day(date) => date,
+            When::Month { year, month } => NaiveDate::from_ymd_opt(year, month, 1).unwrap(),
+            When::Year(year) => NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+            When::Today => today,
+            When::Yesterday => today.pred_opt().unwrap_or(today),
+            When::Ago(span) => span.before(today),
+        }
+    }
+
+    /// The last day covered by `self`, so `2024-02` ends on the 29th.
+    pub fn last_day(&self, today: NaiveDate) -> NaiveDate {
+        let first = self.first_day(today);
+        match self {
+            When::Month { .. } => first + Months::new(1) - Duration::days(1),
+            When::Year(year) => NaiveDate::from_ymd_opt(*year, 12, 31).unwrap(),
+            _ => first,
+        }
+    }
+}
+
+impl FromStr for When {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let trimmed = input.trim().to_lowercase();
+        let error = |detail: &dyn Display| format!("invalid time `{input}`: {detail}");
+        match trimmed.as_str() {
+            "" => return Err(error(&ACCEPTED)),
+            "today" => return Ok(When::Today),
+            "yesterday" => return Ok(When::Yesterday),
+            _ => {}
+        }
+        if !trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(error(&ACCEPTED));
+        }
+        if !trimmed.contains('-') && trimmed.ends_with(|c: char| c.is_ascii_alphabetic()) {
+            return trimmed.parse().map(When::Ago).map_err(|err| error(&err));
+        }
+        let parts = trimmed.split('-').collect::<Vec<_>>();
+        let number = |part: &str, what: &str| {
+            part.parse::<u32>()
+                .map_err(|_| error(&format!("`{part}` is not a valid {what}, {ACCEPTED}")))
+        };
+        let year = number(parts[0], "year")? as i32;
+        if parts[0].len() != 4 {
+            return Err(error(&format!("years need four digits, {ACCEPTED}")));
+        }
+        match parts[1..] {
+            [] => Ok(When::Year(year)),
+            [month] => {
+                let month = number(month, "month")?;
+                if !(1..=12).contains(&month) {
+                    return Err(error(&format!("there is no month {month}")));
+                }
+                Ok(When::Month { year, month })
+            }
+            [month, day] => {
+                let (month, day) = (number(month, "month")?, number(day, "day")?);
+                if !(1..=12).contains(&month) {
+                    return Err(error(&format!("there is no month {month}")));
+                }
+                NaiveDate::from_ymd_opt(year, month, day)
+                    .map(When::Day)
+                    .ok_or_else(|| error(&format!("{year}-{month:02} has no day {day}")))
+            }
+            _ => Err(error(&ACCEPTED)),
+        }
+    }
+}
+
+/// Today's date in the local time zone.
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn span(amount: u32, unit: Unit) -> When {
+        When::Ago(Span { amount, unit })
+    }
+
+    #[test]
+    fn parses_every_form() {
+        let cases = [
+            ("2024-05-01", When::Day(date(2024, 5, 1))),
+            (
+                "2024-05",
+                When::Month {
+                    year: 2024,
+                    month: 5,
+                },
+            ),
+            ("2024", When::Year(2024)),
+            ("today", When::Today),
+            (" Yesterday ", When::Yesterday),
+            ("90d", span(90, Unit::Day)),
+            ("2w", span(2, Unit::Week)),
+            ("6m", span(6, Unit::Month)),
+            ("1y", span(1, Unit::Year)),
+            ("3Weeks", span(3, Unit::Week)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<When>(), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn first_and_last_day_of_months() {
+        let today = date(2025, 1, 1);
+        let cases = [
+            (2024, 2, date(2024, 2, 1), date(2024, 2, 29)),
+            (2023, 2, date(2023, 2, 1), date(2023, 2, 28)),
+            (2024, 12, date(2024, 12, 1), date(2024, 12, 31)),
+        ];
+        for (year, month, first, last) in cases {
+            let when = When::Month { year, month };
+            assert_eq!(when.first_day(today), first, "{year}-{month}");
+            assert_eq!(when.last_day(today), last, "{year}-{month}");
+        }
+        assert_eq!(When::Year(2024).last_day(today), date(2024, 12, 31));
+        assert_eq!(When::Yesterday.first_day(today), date(2024, 12, 31));
+    }
+
+    #[test]
+    fn spans_clamp_to_the_end_of_shorter_months() {
+        let cases = [
+            (date(2024, 3, 31), span(1, Unit::Month), date(2024, 2, 29)),
+            (date(2023, 3, 31), span(1, Unit::Month), date(2023, 2, 28)),
+            (date(2024, 5, 31), span(1, Unit::Month), date(2024, 4, 30)),
+            (date(2024, 2, 29), span(1, Unit::Year), date(2023, 2, 28)),
+            (date(2024, 3, 31), span(2, Unit::Week), date(2024, 3, 17)),
+            (date(2024, 3, 1), span(1, Unit::Day), date(2024, 2, 29)),
+        ];
+        for (today, when, expected) in cases {
+            assert_eq!(when.first_day(today), expected, "{when:?} before {today}");
+        }
+    }
+
+    #[test]
+    fn errors_explain_what_is_wrong() {
+        let cases = [
+            ("", "invalid time ``: expected a date"),
+            ("soon", "invalid time `soon`: expected a date"),
+            ("24-05", "years need four digits"),
+            ("2024-13", "there is no month 13"),
+            ("2024-00-01", "there is no month 0"),
+            ("2023-02-29", "2023-02 has no day 29"),
+            ("2024-xx", "`xx` is not a valid month"),
+            ("5q", "unknown unit `q`"),
+            ("2024-05-01-02", "expected a date"),
+        ];
+        for (input, expected) in cases {
+            let err = input.parse::<When>().unwrap_err();
+            assert!(err.contains(expected), "{input}: {err}");
+        }
+    }
+}
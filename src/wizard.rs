@@ -0,0 +1,79 @@
+use crate::config::{self, DEFAULT_EDITOR, DEFAULT_TUI_SORT};
+use color_eyre::eyre::{anyhow, Result};
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Ask `question` on stdin showing `default` in brackets, which an empty answer accepts.
+pub fn prompt(question: &str, default: &str) -> Result<String> {
+    print!("{question} [{default}]: ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Err(anyhow!("No answer to `{question}`, stdin was closed"));
+    }
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+/// Ask a yes/no question until the answer is one.
+pub fn prompt_bool(question: &str, default: bool) -> Result<bool> {
+    loop {
+        let answer = prompt(question, if default { "yes" } else { "no" })?;
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer yes or no."),
+        }
+    }
+}
+
+/// Walk through the common settings and write them to the config file at `path`.
+/// Existing values, then the environment, are offered as defaults.
+pub fn init_config(path: &Path) -> Result<()> {
+    let file = config::load_file(path)?;
+    println!(
+        "Writing {}, press enter to keep the value in brackets.",
+        path.display()
+    );
+
+    let editor = file
+        .editor
+        .command
+        .clone()
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let editor = prompt("Editor", &editor)?;
+
+    let project_home = file
+        .project_home
+        .clone()
+        .or_else(|| env::var("PROJECT_HOME").ok())
+        .unwrap_or_default();
+    let project_home = prompt("Project home, empty to use $PROJECT_HOME", &project_home)?;
+
+    let sort = file
+        .tui
+        .default_sort
+        .clone()
+        .unwrap_or_else(|| DEFAULT_TUI_SORT.to_string());
+    let sort = loop {
+        let answer = prompt("Default sort", &sort)?;
+        match config::parse_sort(&answer) {
+            Ok(_) => break answer,
+            Err(err) => println!("{err}"),
+        }
+    };
+
+    let color = prompt_bool("Use colours", file.display.color.unwrap_or(true))?;
+
+    config::update_file(path, |file| {
+        file.editor.command = Some(editor);
+        file.project_home = Some(project_home).filter(|home| !home.is_empty());
+        file.tui.default_sort = Some(sort);
+        file.display.color = Some(color);
+        Ok(())
+    })?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,121 @@
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A fresh directory under the system temp dir, removed again on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "vpm-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A projects folder with the usual status folders and its own vpm data dir.
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let dir = TempDir::new();
+        for status in ["Active", "Paused", "Archived"] {
+            fs::create_dir_all(dir.path().join("projects").join(status)).unwrap();
+        }
+        fs::create_dir_all(dir.path().join("data")).unwrap();
+        Self { dir }
+    }
+
+    /// The `$HOME` vpm runs with.
+    pub fn home(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.dir.path().join("projects")
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        self.dir.path().join("data")
+    }
+
+    /// Create `relative` under the projects folder, with any missing parents.
+    pub fn mkdir(&self, relative: &str) -> PathBuf {
+        let path = self.root().join(relative);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    /// Every entry under the projects folder, relative to it and sorted.
+    pub fn tree(&self) -> Vec<String> {
+        fn walk(dir: &Path, root: &Path, out: &mut Vec<String>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                out.push(path.strip_prefix(root).unwrap().display().to_string());
+                if path.is_dir() && !path.is_symlink() {
+                    walk(&path, root, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.root(), &self.root(), &mut out);
+        out.sort();
+        out
+    }
+
+    pub fn vpm(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// `vpm` with `args`, set up to run against this fixture only.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_vpm"));
+        command
+            .args(args)
+            .env("HOME", self.dir.path())
+            .env("PROJECT_HOME", self.root())
+            .env("VPM_DATA_DIR", self.data_dir())
+            .env("NO_COLOR", "1")
+            .env("RUST_BACKTRACE", "0")
+            .env("RUST_LIB_BACKTRACE", "0")
+            .env_remove("VPM_EDITOR")
+            .env_remove("EDITOR")
+            .env_remove("VISUAL")
+            .env_remove("CLICOLOR_FORCE")
+            .stdin(Stdio::null());
+        command
+    }
+
+    pub fn stdout(&self, args: &[&str]) -> String {
+        let output = self.vpm(args);
+        assert!(
+            output.status.success(),
+            "vpm {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::Fixture;
+use std::{fs, process::Output};
+
+fn completions(fixture: &Fixture, args: &[&str]) -> Output {
+    let mut command = fixture.command(&[&["completions"], args].concat());
+    command
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("ZDOTDIR");
+    command.output().unwrap()
+}
+
+#[test]
+fn install_and_uninstall() {
+    let fixture = Fixture::new();
+    let path = fixture.home().join(".config/fish/completions/vpm.fish");
+
+    let output = completions(&fixture, &["fish", "--install"]);
+    assert!(output.status.success(), "{output:?}");
+    let script = fs::read_to_string(&path).unwrap();
+    assert!(script.contains("complete -c vpm"));
+    assert!(script.contains("Managed by vpm"));
+    // vpm owns the file now, so installing again replaces it without asking.
+    assert!(completions(&fixture, &["fish", "--install"])
+        .status
+        .success());
+
+    let output = completions(&fixture, &["fish", "--uninstall"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!path.exists());
+}
+
+#[test]
+fn uninstall_leaves_files_vpm_did_not_write() {
+    let fixture = Fixture::new();
+    let path = fixture.home().join(".zfunc/_vpm");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "#compdef vpm\n").unwrap();
+    let output = completions(&fixture, &["zsh", "--uninstall"]);
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "#compdef vpm\n");
+}
+
+#[test]
+fn unwritable_locations_print_the_script() {
+    let fixture = Fixture::new();
+    // A file where the completions directory should be.
+    fs::create_dir_all(fixture.home().join(".local/share")).unwrap();
+    fs::write(fixture.home().join(".local/share/bash-completion"), "").unwrap();
+    let output = completions(&fixture, &["bash", "--install"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("complete -F"));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Save this script there yourself"));
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::Fixture;
+use std::fs;
+
+#[test]
+fn every_key_round_trips_through_set_and_get() {
+    let fixture = Fixture::new();
+    let root = fixture.root().display().to_string();
+    let gitignore = fixture.data_dir().join("gitignore").display().to_string();
+    let values = [
+        ("project_home", root.as_str()),
+        ("editor.command", "hx"),
+        ("editor.split_command", "tmux split-window {path}"),
+        ("tui.default_sort", "name"),
+        ("list.default_sort", "accessed,name"),
+        ("display.flags", "id,date"),
+        ("trash.retention", "2w"),
+        ("trash.auto_empty", "true"),
+        ("display.color", "false"),
+        ("scan.max_skipped", "0.25"),
+        ("git.gitignore", gitignore.as_str()),
+    ];
+    for (key, value) in values {
+        fixture.stdout(&["config", "set", key, value]);
+    }
+    for (key, value) in values {
+        let output = fixture
+            .command(&["config", "get", key])
+            .env_remove("PROJECT_HOME")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{key}: {output:?}");
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap().trim_end(),
+            value,
+            "{key}"
+        );
+    }
+
+    let output = fixture
+        .command(&["list"])
+        .env_remove("PROJECT_HOME")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[test]
+fn setting_a_key_keeps_the_rest_of_the_file() {
+    let fixture = Fixture::new();
+    fixture.stdout(&["config", "set", "editor.command", "hx"]);
+    let path = fixture.stdout(&["config", "paths"]);
+    let path = path
+        .lines()
+        .find_map(|line| line.strip_prefix("config:"))
+        .unwrap()
+        .trim()
+        .to_string();
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::write(
+        &path,
+        format!("{contents}\n[[kinds]]\nkind = \"ruby\"\nfile = \"Gemfile\"\n"),
+    )
+    .unwrap();
+    fixture.stdout(&["config", "set", "trash.retention", "2w"]);
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("command = \"hx\""), "{contents}");
+    assert!(contents.contains("retention = \"2w\""), "{contents}");
+    assert!(contents.contains("kind = \"ruby\""), "{contents}");
+    let shop = fixture.mkdir("Active/p01-shop-2024-01-01");
+    fs::write(shop.join("Gemfile"), "").unwrap();
+    assert!(fixture.stdout(&["--show-kind", "list"]).contains("ruby"));
+}
+
+#[test]
+fn a_data_dir_override_leaves_the_usual_config_alone() {
+    let fixture = Fixture::new();
+    let legacy = fixture.home().join(".config/vpm/config.toml");
+    fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+    fs::write(&legacy, "[editor]\ncommand = \"hx\"\n").unwrap();
+    let output = fixture
+        .command(&["list"])
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_CACHE_HOME")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert_eq!(
+        fs::read_to_string(&legacy).unwrap(),
+        "[editor]\ncommand = \"hx\"\n"
+    );
+    assert!(!fixture.data_dir().join("config.toml").exists());
+}
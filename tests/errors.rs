@@ -0,0 +1,117 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+use std::{fs, process::Output};
+
+fn error_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn project_not_found() {
+    let fixture = Fixture::new();
+    let output = fixture.vpm(&["info", "7", "--json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        error_json(&output),
+        json!({
+            "schema": 1,
+            "error": { "kind": "ProjectNotFound", "id": 7, "message": "Project 7 not found!" },
+        })
+    );
+}
+
+#[test]
+fn project_home_not_set() {
+    let fixture = Fixture::new();
+    let output = fixture
+        .command(&["--json", "list"])
+        .env_remove("PROJECT_HOME")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let error = &error_json(&output)["error"];
+    assert_eq!(error["kind"], "ProjectHomeNotSet");
+    assert!(error["message"].as_str().unwrap().contains("$PROJECT_HOME"));
+}
+
+#[test]
+fn other_errors() {
+    let fixture = Fixture::new();
+    fs::write(fixture.data_dir().join("config.toml"), "project_home = [").unwrap();
+    let output = fixture.vpm(&["--json", "list"]);
+    assert_eq!(output.status.code(), Some(1));
+    let value = error_json(&output);
+    assert_eq!(value["schema"], 1);
+    assert_eq!(value["error"]["kind"], "Other");
+    assert!(value["error"]["message"].is_string());
+}
+
+#[test]
+fn errors_are_plain_text_without_json() {
+    let fixture = Fixture::new();
+    let output = fixture.vpm(&["info", "7"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Project 7 not found!"));
+}
+
+#[test]
+fn an_unwritable_cache_only_warns() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    fs::create_dir_all(fixture.data_dir()).unwrap();
+    // A file where the cache folder should be.
+    fs::write(fixture.data_dir().join("cache"), "").unwrap();
+    let output = fixture.vpm(&["--show-kind", "list"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "-       \tapp\t\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Warning: could not update the cache"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn invalid_names() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    let commands: [&[&str]; 3] = [
+        &["--json", "new", "a/b"],
+        &["--json", "rename", "1", "a/b"],
+        &["--json", "duplicate", "1", "a/b"],
+    ];
+    for args in commands {
+        let output = fixture.vpm(args);
+        assert_eq!(output.status.code(), Some(1), "{args:?}");
+        let error = &error_json(&output)["error"];
+        assert_eq!(error["kind"], "InvalidName", "{args:?}");
+        assert_eq!(error["name"], "a/b", "{args:?}");
+        assert!(error["reason"].is_string(), "{args:?}");
+    }
+    assert_eq!(
+        fixture.tree(),
+        ["Active", "Active/p01-app-2024-01-01", "Archived", "Paused"]
+    );
+}
+
+#[test]
+fn moving_several_projects_fails_if_any_is_missing() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    let output = fixture.vpm(&["archive", "1", "99"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: Project 99 not found!"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Failed to move 99 to Archived"), "{stderr}");
+    // The other projects are still moved.
+    assert!(fixture.root().join("Archived/p01-app-2024-01-01").is_dir());
+}
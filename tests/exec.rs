@@ -0,0 +1,78 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-good-2024-01-01");
+    fixture.mkdir("Active/p02-bad-2024-01-01");
+    fixture.mkdir("Paused/p03-idle-2024-01-01");
+    fixture
+}
+
+/// Prints the folder's name, and fails in the `bad` project.
+const SCRIPT: &str = "basename \"$PWD\"; case \"$PWD\" in *bad*) echo oops >&2; exit 3;; esac";
+
+#[test]
+fn prefixes_output_and_summarizes() {
+    let fixture = fixture();
+    let output = fixture.vpm(&["exec", "--all", "--", "sh", "-c", SCRIPT]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[p01 good] p01-good-2024-01-01\n\
+         [p02 bad] p02-bad-2024-01-01\n\
+         [p03 idle] p03-idle-2024-01-01\n\
+         \n\
+         ok       p01-good-2024-01-01\n\
+         failed   p02-bad-2024-01-01 (exit 3)\n\
+         ok       p03-idle-2024-01-01\n\
+         2 succeeded, 1 failed, 0 skipped\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("[p02 bad] oops\n"), "{stderr}");
+    assert!(stderr.contains("1 of 3 projects failed"), "{stderr}");
+}
+
+#[test]
+fn fail_fast_skips_the_rest() {
+    let fixture = fixture();
+    let output = fixture.vpm(&["exec", "--all", "--fail-fast", "--", "sh", "-c", SCRIPT]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("[p03 idle]"), "{stdout}");
+    assert!(stdout.ends_with("skipped  p03-idle-2024-01-01\n1 succeeded, 1 failed, 1 skipped\n"));
+}
+
+#[test]
+fn parallel_jobs_keep_whole_lines() {
+    let fixture = fixture();
+    let output = fixture.vpm(&[
+        "exec",
+        "--all",
+        "--st",
+        "active",
+        "-j",
+        "2",
+        "--",
+        "sh",
+        "-c",
+        "echo one; echo two",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines().take(4).collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            "[p01 good] one",
+            "[p01 good] two",
+            "[p02 bad] one",
+            "[p02 bad] two"
+        ]
+    );
+    assert!(stdout.ends_with("2 succeeded, 0 failed, 0 skipped\n"));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::Fixture;
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn info_says_where_an_exported_project_went() {
+    let fixture = Fixture::new();
+    let project = fixture.mkdir("Active/p01-app-2024-01-01");
+    fs::write(project.join("README.md"), "hi").unwrap();
+    let to = fixture.home().join("cold-storage");
+    fixture.stdout(&["archive", "1", "--to", to.to_str().unwrap(), "-m", "done"]);
+    assert!(!project.exists());
+
+    let archive = to.join("p01-app-2024-01-01.tar.zst");
+    let info = fixture.stdout(&["info", "1"]);
+    assert!(info.starts_with("p01-app-2024-01-01: "), "{info}");
+    assert!(
+        info.contains(&format!("exported to {}", archive.display())),
+        "{info}"
+    );
+    assert!(info.ends_with(": \"done\"\n"), "{info}");
+
+    let json: Value = serde_json::from_str(&fixture.stdout(&["--json", "info", "1"])).unwrap();
+    assert_eq!(json["tombstone"]["destination"], archive.to_str().unwrap());
+    assert_eq!(json["tombstone"]["id"], 1);
+
+    let output = fixture.vpm(&["info", "2"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn a_failed_export_exits_non_zero_and_keeps_the_project() {
+    let fixture = Fixture::new();
+    let project = fixture.mkdir("Active/p01-app-2024-01-01");
+    let to = fixture.home().join("cold-storage");
+    fs::create_dir_all(&to).unwrap();
+    // An archive already at the destination is never overwritten.
+    fs::write(to.join("p01-app-2024-01-01.tar.zst"), "taken").unwrap();
+
+    let output = fixture.vpm(&["archive", "1", "2", "--to", to.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already exists"), "{stderr}");
+    assert!(stderr.contains("Project 2 not found"), "{stderr}");
+    assert!(stderr.contains("Failed to archive 1, 2"), "{stderr}");
+    assert!(project.exists());
+}
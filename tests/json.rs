@@ -0,0 +1,199 @@
+mod common;
+
+use common::Fixture;
+use serde_json::{json, Value};
+use std::fs;
+
+/// Parse `vpm` output as JSON, dropping `last_accessed` and `last_activity`, which follow the
+/// folders' atime.
+fn parse(output: &str) -> Value {
+    let mut value: Value = serde_json::from_str(output).unwrap();
+    fn strip(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.remove("last_accessed");
+                map.remove("last_activity");
+                map.values_mut().for_each(strip);
+            }
+            Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    strip(&mut value);
+    value
+}
+
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-foo-2024-01-15");
+    fixture.mkdir("Paused/p0A-project-10-2021-01-01");
+    fixture
+}
+
+#[test]
+fn list_json() {
+    let fixture = fixture();
+    let root = fixture.root().display().to_string();
+    assert_eq!(
+        parse(&fixture.stdout(&["--json", "list"])),
+        json!({
+            "schema": 1,
+            "projects": [
+                {
+                    "id": 1,
+                    "name": "foo",
+                    "full_name": "p01-foo-2024-01-15",
+                    "date": "2024-01-15",
+                    "path": format!("{root}/Active/p01-foo-2024-01-15"),
+                    "status": "Active",
+                },
+                {
+                    "id": 10,
+                    "name": "project-10",
+                    "full_name": "p0A-project-10-2021-01-01",
+                    "date": "2021-01-01",
+                    "path": format!("{root}/Paused/p0A-project-10-2021-01-01"),
+                    "status": "Paused",
+                },
+            ],
+        })
+    );
+}
+
+#[test]
+fn info_json() {
+    let fixture = fixture();
+    let root = fixture.root().display().to_string();
+    assert_eq!(
+        parse(&fixture.stdout(&["info", "0xA", "--json", "--no-size"])),
+        json!({
+            "schema": 1,
+            "id": 10,
+            "name": "project-10",
+            "full_name": "p0A-project-10-2021-01-01",
+            "date": "2021-01-01",
+            "path": format!("{root}/Paused/p0A-project-10-2021-01-01"),
+            "status": "Paused",
+            "accessed_source": "atime",
+            "priority": null,
+            "size": null,
+            "template": null,
+        })
+    );
+}
+
+#[test]
+fn rename_json() {
+    let fixture = fixture();
+    let root = fixture.root().display().to_string();
+    assert_eq!(
+        parse(&fixture.stdout(&["rename", "1", "bar", "--json"])),
+        json!({
+            "schema": 1,
+            "id": 1,
+            "name": "bar",
+            "full_name": "p01-bar-2024-01-15",
+            "path": format!("{root}/Active/p01-bar-2024-01-15"),
+            "date": "2024-01-15",
+            "status": "Active",
+        })
+    );
+}
+
+#[test]
+fn new_and_duplicate_json() {
+    let fixture = fixture();
+    let new = parse(&fixture.stdout(&["--json", "new", "App"]));
+    assert_eq!(
+        (&new["schema"], &new["id"], &new["name"]),
+        (&json!(1), &json!(11), &json!("app"))
+    );
+    assert_eq!(new["status"], "Active");
+    let copy = parse(&fixture.stdout(&["--json", "duplicate", "1", "copy"]));
+    assert_eq!((&copy["id"], &copy["name"]), (&json!(12), &json!("copy")));
+    assert!(std::path::Path::new(copy["path"].as_str().unwrap()).is_dir());
+}
+
+#[test]
+fn search_json() {
+    let fixture = fixture();
+    let root = fixture.root().display().to_string();
+    assert_eq!(
+        parse(&fixture.stdout(&["--json", "search", "foo"])),
+        json!({
+            "schema": 1,
+            "projects": [
+                {
+                    "id": 1,
+                    "name": "foo",
+                    "full_name": "p01-foo-2024-01-15",
+                    "date": "2024-01-15",
+                    "path": format!("{root}/Active/p01-foo-2024-01-15"),
+                    "status": "Active",
+                },
+            ],
+        })
+    );
+}
+
+#[test]
+fn path_json() {
+    let fixture = fixture();
+    let root = fixture.root().display().to_string();
+    assert_eq!(
+        parse(&fixture.stdout(&["--json", "path", "0xA"])),
+        json!({
+            "schema": 1,
+            "id": 10,
+            "name": "project-10",
+            "full_name": "p0A-project-10-2021-01-01",
+            "date": "2021-01-01",
+            "path": format!("{root}/Paused/p0A-project-10-2021-01-01"),
+            "status": "Paused",
+            "exists": true,
+        })
+    );
+}
+
+#[test]
+fn stats_json() {
+    let fixture = fixture();
+    assert_eq!(
+        parse(&fixture.stdout(&["--json", "stats"])),
+        json!({
+            "schema": 1,
+            "buckets": [
+                { "name": "Active", "stats": { "projects": 1, "size": 0 } },
+                { "name": "Paused", "stats": { "projects": 1, "size": 0 } },
+            ],
+            "highlights": {
+                "newest": { "id": 1, "name": "foo", "date": "2024-01-15T00:00:00Z" },
+                "oldest": { "id": 10, "name": "project-10", "date": "2021-01-01T00:00:00Z" },
+            },
+            "total": { "projects": 2, "size": 0 },
+        })
+    );
+}
+
+#[test]
+fn tag_stats_json() {
+    let fixture = fixture();
+    for (project, tags) in [
+        ("Active/p01-foo-2024-01-15", r#"["rust", "cli"]"#),
+        ("Paused/p0A-project-10-2021-01-01", r#"["rust"]"#),
+    ] {
+        let dir = fixture.root().join(project).join(".vpm");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("meta.toml"), format!("tags = {tags}\n")).unwrap();
+    }
+    assert_eq!(
+        parse(&fixture.stdout(&["--json", "tag", "stats"])),
+        json!({
+            "schema": 1,
+            "tags": [
+                { "tag": "rust", "count": 2 },
+                { "tag": "cli", "count": 1 },
+            ],
+        })
+    );
+}
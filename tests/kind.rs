@@ -0,0 +1,20 @@
+mod common;
+
+use common::Fixture;
+use std::fs;
+
+#[test]
+fn metadata_kind_beats_inference() {
+    let fixture = Fixture::new();
+    let inferred = fixture.mkdir("Active/p01-inferred-2024-01-01");
+    fs::write(inferred.join("Cargo.toml"), "").unwrap();
+    let overridden = fixture.mkdir("Active/p02-overridden-2024-01-01");
+    fs::write(overridden.join("Cargo.toml"), "").unwrap();
+    fs::create_dir(overridden.join(".vpm")).unwrap();
+    fs::write(overridden.join(".vpm/meta.toml"), "kind = \"web\"\n").unwrap();
+    fixture.mkdir("Active/p03-unknown-2024-01-01");
+    assert_eq!(
+        fixture.stdout(&["--show-kind", "list"]),
+        "rust    \tinferred\t\nweb     \toverridden\t\n-       \tunknown\t\n"
+    );
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn invalid_names_create_nothing() {
+    let fixture = Fixture::new();
+    let before = fixture.tree();
+    for name in ["日本", "a/b", "a\\b", "--", "..", "  "] {
+        let output = fixture.vpm(&["new", "--", name]);
+        assert!(!output.status.success(), "`{name}` was accepted");
+        assert_eq!(fixture.tree(), before, "`{name}` left folders behind");
+    }
+}
+
+#[test]
+fn valid_name_is_normalized() {
+    let fixture = Fixture::new();
+    fixture.stdout(&["new", "My Project"]);
+    assert!(fixture
+        .tree()
+        .iter()
+        .any(|entry| entry.starts_with("Active/p01-my-project-")));
+}
+
+#[test]
+fn new_projects_get_a_meta_file() {
+    let fixture = Fixture::new();
+    fixture.stdout(&["new", "with-meta"]);
+    let project = fixture
+        .tree()
+        .into_iter()
+        .find(|entry| entry.starts_with("Active/p01-with-meta-") && !entry.contains(".vpm"))
+        .unwrap();
+    let date = &project[project.len() - 10..];
+    let meta =
+        std::fs::read_to_string(fixture.root().join(&project).join(".vpm/meta.toml")).unwrap();
+    assert_eq!(
+        meta,
+        format!("created = \"{date}\"\ndescription = \"\"\ntags = []\n")
+    );
+}
+
+#[test]
+fn folders_without_meta_still_load() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-old-2020-01-01");
+    fixture.stdout(&["new", "tagged"]);
+    fixture.stdout(&["tag", "2", "rust"]);
+    assert_eq!(fixture.stdout(&["-D", "list"]), "old\t-\t\ntagged\t-\t\n");
+    assert_eq!(fixture.stdout(&["search", "--tag", "rust"]), "tagged\t\n");
+}
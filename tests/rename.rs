@@ -0,0 +1,35 @@
+mod common;
+
+use common::Fixture;
+use std::fs;
+
+#[test]
+fn renaming_keeps_the_id_width_of_the_folder() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    // A three digit id makes new folders pad to three digits.
+    fixture.mkdir("Active/p100-big-2024-01-01");
+    fixture.stdout(&["rename", "1", "web"]);
+    assert!(fixture.root().join("Active/p01-web-2024-01-01").is_dir());
+}
+
+#[test]
+fn fixing_a_folder_date_keeps_the_id_width() {
+    let fixture = Fixture::new();
+    let project = fixture.mkdir("Active/p01-app-2024-01-01");
+    fixture.mkdir("Active/p100-big-2024-01-01");
+    fs::create_dir(project.join(".vpm")).unwrap();
+    fs::write(project.join(".vpm/meta.toml"), "created = \"2024-02-02\"\n").unwrap();
+    fixture.stdout(&["doctor", "--fix", "folder"]);
+    assert!(fixture.root().join("Active/p01-app-2024-02-02").is_dir());
+}
+
+#[test]
+fn renaming_keeps_the_status() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Paused/p01-app-2024-01-01");
+    fixture.stdout(&["rename", "1", "web"]);
+    assert!(fixture.root().join("Paused/p01-web-2024-01-01").is_dir());
+    assert!(!fixture.root().join("Active/p01-web-2024-01-01").exists());
+    assert!(fixture.stdout(&["status", "1"]).contains("Paused"));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::Fixture;
+use std::fs;
+
+#[test]
+fn only_folders_directly_in_status_folders_are_projects() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01/p05-vendored-2024-01-01");
+    fixture.mkdir("Paused/p02-old-2023-01-01");
+    fs::write(fixture.root().join("Active/p03-file-2024-01-01"), "").unwrap();
+    fs::write(fixture.root().join("Archived/notes.txt"), "").unwrap();
+    fixture.mkdir("Archived/.hidden");
+    fixture.mkdir("p04-outside-2024-01-01");
+    assert_eq!(fixture.stdout(&["-i", "list"]), "  1\tapp\t\n  2\told\t\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_loops_are_not_followed() {
+    use std::os::unix::fs::symlink;
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    // A project that links back to its own status folder, and one inside itself.
+    symlink("..", fixture.root().join("Active/p02-loop-2024-01-01")).unwrap();
+    symlink(".", fixture.root().join("Active/p01-app-2024-01-01/self")).unwrap();
+    assert_eq!(fixture.stdout(&["-i", "list"]), "  1\tapp\t\n  2\tloop\t\n");
+    let output = fixture.vpm(&["info", "2", "--no-size"]);
+    assert!(output.status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_status_folders_are_read() {
+    use std::os::unix::fs::symlink;
+    let fixture = Fixture::new();
+    fs::remove_dir(fixture.root().join("Archived")).unwrap();
+    let elsewhere = fixture.root().parent().unwrap().join("archive-disk");
+    fs::create_dir_all(elsewhere.join("p07-frozen-2020-01-01")).unwrap();
+    symlink(&elsewhere, fixture.root().join("Archived")).unwrap();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    assert_eq!(
+        fixture.stdout(&["-i", "-s", "list"]),
+        "  1\t(Active)\tapp\t\n  7\t(Archived)\tfrozen\t\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn a_symlinked_project_home_keeps_its_path() {
+    use std::os::unix::fs::symlink;
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    let link = fixture.root().parent().unwrap().join("linked-home");
+    symlink(fixture.root(), &link).unwrap();
+    let vpm = |args: &[&str]| {
+        let output = fixture
+            .command(args)
+            .env("PROJECT_HOME", &link)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        (
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    let (list, warnings) = vpm(&["-i", "list"]);
+    assert_eq!(list, "  1\tapp\t\n");
+    assert_eq!(warnings, "");
+    let (path, _) = vpm(&["path", "1"]);
+    assert_eq!(
+        path.trim_end(),
+        link.join("Active/p01-app-2024-01-01").to_str().unwrap()
+    );
+    vpm(&["new", "fresh"]);
+    let (path, _) = vpm(&["path", "2"]);
+    assert!(path.starts_with(link.to_str().unwrap()), "{path}");
+    assert!(fixture.root().join("Active").read_dir().unwrap().count() == 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn status_folders_pointing_at_the_home_or_each_other_are_skipped() {
+    use std::os::unix::fs::symlink;
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    fs::remove_dir(fixture.root().join("Paused")).unwrap();
+    fs::remove_dir(fixture.root().join("Archived")).unwrap();
+    symlink(fixture.root().join("Active"), fixture.root().join("Paused")).unwrap();
+    symlink(fixture.root(), fixture.root().join("Archived")).unwrap();
+
+    let output = fixture.vpm(&["-i", "list"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "  1\tapp\t\n");
+    let warnings = String::from_utf8(output.stderr).unwrap();
+    assert!(warnings.contains("Paused, it points to"), "{warnings}");
+    assert!(warnings.contains("already scanned"), "{warnings}");
+    assert!(
+        warnings.contains("Archived, it points to the project home"),
+        "{warnings}"
+    );
+}
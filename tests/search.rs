@@ -0,0 +1,19 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn displayed_paths_and_dates_are_not_searched() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-app-2024-01-01");
+    assert_eq!(
+        fixture.stdout(&["-p", "search", "app"]),
+        format!(
+            "{}\n",
+            fixture.root().join("Active/p01-app-2024-01-01").display()
+        )
+    );
+    // Only the displayed path has "active" in it, and only the displayed date has "2024".
+    assert_eq!(fixture.stdout(&["-p", "search", "active"]), "");
+    assert_eq!(fixture.stdout(&["-d", "search", "2024"]), "");
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn hex_looking_names_are_names() {
+    let fixture = Fixture::new();
+    fixture.mkdir("Active/p01-pdf-2024-01-01");
+    fixture.stdout(&["rename", "pdf", "pdf2"]);
+    assert_eq!(
+        fixture.tree(),
+        ["Active", "Active/p01-pdf2-2024-01-01", "Archived", "Paused",]
+    );
+    let output = fixture.vpm(&["rename", "pdf", "pdf3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Project 223 not found!"));
+}
+
+#[test]
+fn path_finds_hex_looking_names() {
+    let fixture = Fixture::new();
+    let pdf = fixture.mkdir("Active/p01-pdf-2024-01-01");
+    assert_eq!(
+        fixture.stdout(&["path", "pdf"]).trim_end(),
+        pdf.to_str().unwrap()
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::Fixture;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Every file under `dir` with its contents, keyed by its path relative to `dir`, skipping the
+/// `.vpm` folder.
+fn files(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    fn walk(dir: &Path, root: &Path, out: &mut BTreeMap<PathBuf, Vec<u8>>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let relative = path.strip_prefix(root).unwrap().to_path_buf();
+            if relative == Path::new(".vpm") {
+                continue;
+            }
+            if path.is_dir() {
+                out.insert(relative, Vec::new());
+                walk(&path, root, out);
+            } else {
+                out.insert(relative, fs::read(&path).unwrap());
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(dir, dir, &mut out);
+    out
+}
+
+fn project_path(fixture: &Fixture, id: &str) -> PathBuf {
+    PathBuf::from(fixture.stdout(&["path", id]).trim_end())
+}
+
+#[test]
+fn export_wipe_import_round_trip() {
+    let fixture = Fixture::new();
+    let template = fixture.mkdir("templates/rust-service");
+    fs::create_dir_all(template.join("src/bin")).unwrap();
+    fs::write(template.join("Cargo.toml"), "[package]\nname = \"svc\"\n").unwrap();
+    fs::write(template.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(template.join("src/bin/tool.rs"), [0, 1, 2, 255]).unwrap();
+    fs::create_dir(template.join("empty")).unwrap();
+    let archive = fixture.root().join("rust-service.tar.zst");
+    let archive = archive.to_str().unwrap();
+
+    fixture.stdout(&["template", "export", "rust-service", "-o", archive]);
+    fixture.stdout(&["new", "svc", "--template", "rust-service"]);
+    let before = files(&project_path(&fixture, "1"));
+    fs::remove_dir_all(fixture.root().join("templates")).unwrap();
+
+    fixture.stdout(&["template", "import", archive]);
+    assert!(fixture
+        .stdout(&["template", "list"])
+        .starts_with("rust-service "));
+    fixture.stdout(&["new", "svc", "--template", "rust-service"]);
+    let after = files(&project_path(&fixture, "2"));
+
+    assert_eq!(before, after);
+    assert_eq!(after[Path::new("src/bin/tool.rs")], [0, 1, 2, 255]);
+    assert!(after.contains_key(Path::new("empty")));
+}
+
+#[test]
+fn import_suffixes_or_replaces_an_existing_template() {
+    let fixture = Fixture::new();
+    let template = fixture.mkdir("templates/web");
+    fs::write(template.join("index.html"), "exported").unwrap();
+    let archive = fixture.root().join("web.tar.zst");
+    let archive = archive.to_str().unwrap();
+    fixture.stdout(&["template", "export", "web", "-o", archive]);
+    fs::write(template.join("index.html"), "local").unwrap();
+
+    fixture.stdout(&["template", "import", archive]);
+    let templates = fixture.root().join("templates");
+    assert_eq!(
+        fs::read_to_string(templates.join("web/index.html")).unwrap(),
+        "local"
+    );
+    assert_eq!(
+        fs::read_to_string(templates.join("web-2/index.html")).unwrap(),
+        "exported"
+    );
+
+    fixture.stdout(&["template", "import", archive, "--force"]);
+    assert_eq!(
+        fs::read_to_string(templates.join("web/index.html")).unwrap(),
+        "exported"
+    );
+    assert!(!templates.join("web-3").exists());
+}
+
+#[test]
+fn junk_in_the_templates_folder_is_not_a_template() {
+    let fixture = Fixture::new();
+    fixture.mkdir("templates/rust");
+    let templates = fixture.root().join("templates");
+    fs::write(templates.join("README.md"), "").unwrap();
+    fs::write(templates.join(".DS_Store"), "").unwrap();
+
+    let list = fixture.stdout(&["template", "list"]);
+    assert_eq!(list.lines().count(), 1, "{list}");
+    assert!(list.starts_with("rust "), "{list}");
+
+    let output = fixture.vpm(&["new", "app", "--template", "README.md"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Template README.md is not a folder!"));
+    assert!(fixture
+        .root()
+        .join("Active")
+        .read_dir()
+        .unwrap()
+        .next()
+        .is_none());
+
+    let doctor = String::from_utf8(fixture.vpm(&["doctor"]).stdout).unwrap();
+    assert!(
+        doctor.contains("templates/README.md: not a folder"),
+        "{doctor}"
+    );
+    assert!(!doctor.contains(".DS_Store"), "{doctor}");
+}